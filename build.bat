@@ -10,8 +10,6 @@ cargo build --release || exit 1
 
 popd || exit 1
 
-copy starter\target\release\starter.exe build\clash-multiplatform.exe || exit 1
-
 rmdir /S /Q build\jre
 
 jlink --add-modules java.base,java.desktop,java.logging ^
@@ -20,10 +18,7 @@ jlink --add-modules java.base,java.desktop,java.logging ^
   --no-header-files --no-man-pages --strip-debug ^
   --verbose || exit 1
 
-pushd build || exit 1
-
-zip -r bundle.zip * || exit 1
-
-popd || exit 1
-
-move build\bundle.zip bundle.zip || exit 1
+starter\target\release\starter.exe bundle ^
+  --starter starter\target\release\starter.exe ^
+  --runtime build\jre ^
+  --output bundle.zip || exit 1
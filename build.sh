@@ -10,20 +10,32 @@ popd || exit 1
 
 mkdir -p build
 
-cp starter/target/release/starter build/clash-multiplatform || exit 1
-
 rm -rf build/jre
 
-jlink --add-modules java.base,java.desktop,java.logging \
+# HEADLESS=1 leaves out java.desktop, starter then launches headless.
+MODULES=java.base,java.desktop,java.logging
+if [ -n "$HEADLESS" ]; then
+  MODULES=java.base,java.logging
+fi
+
+jlink --add-modules "$MODULES" \
   --output build/jre \
   --ignore-signing-information \
   --no-header-files --no-man-pages --strip-debug \
   --verbose || exit 1
 
-pushd build || exit 1
-
-zip -r bundle.zip ./* || exit 1
-
-popd || exit 1
-
-mv build/bundle.zip ./bundle.zip
+starter/target/release/starter bundle \
+  --starter starter/target/release/starter \
+  --runtime build/jre \
+  --output bundle.zip || exit 1
+
+starter/target/release/starter bundle \
+  --starter starter/target/release/starter \
+  --runtime build/jre \
+  --output bundle.tar.zst || exit 1
+
+starter/target/release/starter bundle \
+  --starter starter/target/release/starter \
+  --runtime build/jre \
+  --format self-extracting \
+  --output clash-multiplatform.run || exit 1
@@ -1,5 +1,8 @@
 extern crate embed_resource;
 
 fn main() {
+    println!("cargo:rerun-if-env-changed=CLASH_STARTER_PUBLIC_KEY");
+    println!("cargo:rerun-if-env-changed=CLASH_STARTER_SIGNATURE_POLICY");
+
     embed_resource::compile("extra/app.rc", embed_resource::NONE);
 }
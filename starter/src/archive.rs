@@ -0,0 +1,37 @@
+use std::{
+    error::Error,
+    fs::File,
+    io::{Read, Seek, SeekFrom},
+    path::Path,
+};
+
+const ZIP_MAGIC: &[u8] = b"PK\x03\x04";
+const GZIP_MAGIC: &[u8] = b"\x1f\x8b";
+const ZSTD_MAGIC: &[u8] = b"\x28\xb5\x2f\xfd";
+
+pub fn unpack_tar_zst<R: Read>(reader: R, dest: &Path) -> Result<(), Box<dyn Error>> {
+    tar::Archive::new(zstd::Decoder::new(reader)?).unpack(dest)?;
+
+    Ok(())
+}
+
+pub fn extract_archive(archive: &Path, dest: &Path) -> Result<(), Box<dyn Error>> {
+    let mut file = File::open(archive)?;
+
+    let mut magic = [0u8; 4];
+    let length = file.read(&mut magic)?;
+    file.seek(SeekFrom::Start(0))?;
+
+    let magic = &magic[..length];
+    if magic.starts_with(ZIP_MAGIC) {
+        zip::ZipArchive::new(file)?.extract(dest)?;
+    } else if magic.starts_with(GZIP_MAGIC) {
+        tar::Archive::new(flate2::read::GzDecoder::new(file)).unpack(dest)?;
+    } else if magic.starts_with(ZSTD_MAGIC) {
+        unpack_tar_zst(file, dest)?;
+    } else {
+        return Err("Unsupported archive format".into());
+    }
+
+    Ok(())
+}
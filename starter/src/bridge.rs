@@ -0,0 +1,100 @@
+use std::{
+    ffi::c_void,
+    path::{Path, PathBuf},
+    sync::OnceLock,
+};
+
+use cstr::cstr;
+use jni_sys::{jboolean, jclass, jint, jobject, jstring, JNIEnv, JNINativeMethod, JNI_FALSE, JNI_OK, JNI_TRUE};
+
+use crate::{
+    deeplink::new_java_action_map,
+    dirs::desktop_entry_id,
+    instance::wait_message,
+    metadata::Metadata,
+    notification::{show_notification, Notification, Urgency},
+    update::pending::confirm_update,
+    utils::java::{jcall, JStringExt},
+};
+
+static APP_DIR: OnceLock<PathBuf> = OnceLock::new();
+static APP_NAME_AND_ID: OnceLock<(String, String)> = OnceLock::new();
+
+extern "system" fn confirm_startup(_env: *mut JNIEnv, _class: jclass) {
+    if let Some(app_dir) = APP_DIR.get() {
+        let _ = confirm_update(app_dir);
+    }
+}
+
+// Blocks until another launch forwards a deep link or an activation request.
+extern "system" fn wait_instance_message(env: *mut JNIEnv, _class: jclass) -> jobject {
+    new_java_action_map(env, &wait_message().fields())
+}
+
+extern "system" fn show_app_notification(
+    env: *mut JNIEnv,
+    _class: jclass,
+    summary: jstring,
+    body: jstring,
+    urgency: jint,
+) -> jboolean {
+    let (Some((app_name, app_id)), false, false) = (APP_NAME_AND_ID.get(), summary.is_null(), body.is_null()) else {
+        return JNI_FALSE;
+    };
+
+    let notification = Notification {
+        app_name,
+        app_id,
+        summary: &String::from_java_string(env, summary),
+        body: &String::from_java_string(env, body),
+        urgency: match urgency {
+            0 => Urgency::Low,
+            1 => Urgency::Normal,
+            _ => Urgency::Critical,
+        },
+    };
+
+    if show_notification(&notification).is_ok() {
+        JNI_TRUE
+    } else {
+        JNI_FALSE
+    }
+}
+
+// Binds native methods of optional `NativeStarter` class, skipped if app does not ship it.
+pub fn register_natives(env: *mut JNIEnv, app_dir: &Path, metadata: &Metadata, profile: &str) {
+    let _ = APP_DIR.set(app_dir.to_path_buf());
+    let _ = APP_NAME_AND_ID.set((metadata.name.clone(), desktop_entry_id(&metadata.edition, profile)));
+
+    let class = jcall!(env, FindClass, cstr!("com/github/kr328/clash/NativeStarter").as_ptr());
+    if class.is_null() {
+        jcall!(env, ExceptionClear);
+
+        return;
+    }
+
+    let methods = [
+        JNINativeMethod {
+            name: cstr!("confirmStartup").as_ptr().cast_mut(),
+            signature: cstr!("()V").as_ptr().cast_mut(),
+            fnPtr: confirm_startup as *mut c_void,
+        },
+        JNINativeMethod {
+            name: cstr!("waitInstanceMessage").as_ptr().cast_mut(),
+            signature: cstr!("()Ljava/util/Map;").as_ptr().cast_mut(),
+            fnPtr: wait_instance_message as *mut c_void,
+        },
+        JNINativeMethod {
+            name: cstr!("showNotification").as_ptr().cast_mut(),
+            signature: cstr!("(Ljava/lang/String;Ljava/lang/String;I)Z").as_ptr().cast_mut(),
+            fnPtr: show_app_notification as *mut c_void,
+        },
+    ];
+
+    // One by one, apps built against older starters declare only some of them.
+    for method in &methods {
+        if jcall!(env, RegisterNatives, class, method, 1) != JNI_OK {
+            jcall!(env, ExceptionClear);
+        }
+    }
+}
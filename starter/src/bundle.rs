@@ -0,0 +1,555 @@
+use std::{
+    collections::BTreeMap,
+    error::Error,
+    fs::{File, OpenOptions},
+    io::{self, Read, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+};
+
+use sha2::{Digest, Sha256};
+use zip::{write::FileOptions, CompressionMethod, DateTime, ZipArchive, ZipWriter};
+
+use crate::{
+    desktop::ICONS_DIR_NAME,
+    integrity::{hash_file, BUNDLE_HASHES_NAME},
+    metadata::resolve_app_metadata,
+    options::{BundleFormat, BundleOptions},
+    payload::{
+        hash_region, read_index, read_trailer, write_index, write_trailer, IndexEntry, APP_JAR_SIGNATURE_NAME,
+        PAYLOAD_ARCHIVE_NAME,
+    },
+    signature::{signature_path, verify_signatures},
+    utils::strings::encode_hex,
+    APP_JAR_NAME, APP_LIB_DIR_NAME,
+};
+
+pub const STARTER_NAME: &str = "clash-multiplatform";
+pub const RUNTIME_DIR_NAME: &str = "jre";
+const FILE_MODE: u32 = 0o644;
+const EXECUTABLE_MODE: u32 = 0o755;
+const LINK_MODE: u32 = 0o777;
+
+// 1980-01-01, the earliest timestamp zip can store, used for tar too so extracted trees look the same.
+const BUNDLE_MTIME: u64 = 315532800;
+const ZSTD_LEVEL: i32 = 19;
+
+// Stored uncompressed next to the archive of self-extracting bundles, loaded without extraction.
+const EMBEDDED_ENTRIES: &[&str] = &[APP_JAR_NAME, APP_JAR_SIGNATURE_NAME];
+
+// Runtime files that must be executable even if the image was built on a filesystem without modes.
+const RUNTIME_EXECUTABLES: &[&str] = &["jre/bin/", "jre/lib/jspawnhelper"];
+
+enum EntryContent {
+    File(PathBuf),
+    Generated(Vec<u8>),
+    Symlink(PathBuf),
+}
+
+struct BundleEntry {
+    content: EntryContent,
+    mode: u32,
+}
+
+#[derive(PartialEq)]
+enum ArchivedContent {
+    Hash(String),
+    Link(PathBuf),
+}
+
+#[derive(PartialEq)]
+struct ArchivedEntry {
+    mode: u32,
+    content: ArchivedContent,
+}
+
+impl BundleEntry {
+    fn file(source: PathBuf, mode: u32) -> BundleEntry {
+        BundleEntry {
+            content: EntryContent::File(source),
+            mode,
+        }
+    }
+}
+
+#[cfg(unix)]
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+
+    std::fs::metadata(path).is_ok_and(|m| m.permissions().mode() & 0o111 != 0)
+}
+
+#[cfg(not(unix))]
+fn is_executable(_: &Path) -> bool {
+    false
+}
+
+fn mode_of(name: &str, source: &Path) -> u32 {
+    let forced = RUNTIME_EXECUTABLES.iter().any(|prefix| match prefix.strip_suffix('/') {
+        Some(dir) => name
+            .strip_prefix(dir)
+            .is_some_and(|rest| rest.starts_with('/') && !rest[1..].contains('/')),
+        None => name == *prefix,
+    });
+
+    if forced || is_executable(source) {
+        EXECUTABLE_MODE
+    } else {
+        FILE_MODE
+    }
+}
+
+fn collect_dir(
+    dir: &Path,
+    prefix: &str,
+    keep_links: bool,
+    entries: &mut BTreeMap<String, BundleEntry>,
+) -> Result<(), Box<dyn Error>> {
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        let name = entry
+            .file_name()
+            .into_string()
+            .map_err(|name| format!("Non UTF-8 file name: {}", name.to_string_lossy()))?;
+        let name = format!("{}/{}", prefix, name);
+        let path = entry.path();
+
+        // Zip archives keep file content only, so symbolic links are followed there.
+        if keep_links && entry.file_type()?.is_symlink() {
+            let target = std::fs::read_link(&path)?;
+
+            entries.insert(
+                name,
+                BundleEntry {
+                    content: EntryContent::Symlink(target),
+                    mode: LINK_MODE,
+                },
+            );
+        } else if std::fs::metadata(&path)?.is_dir() {
+            collect_dir(&path, &name, keep_links, entries)?;
+        } else {
+            let mode = mode_of(&name, &path);
+
+            entries.insert(name, BundleEntry::file(path, mode));
+        }
+    }
+
+    Ok(())
+}
+
+fn collect_entries(
+    options: &BundleOptions,
+    format: BundleFormat,
+    starter: &Path,
+) -> Result<BTreeMap<String, BundleEntry>, Box<dyn Error>> {
+    let mut entries = BTreeMap::new();
+    let keep_links = format != BundleFormat::Zip;
+
+    // Self-extracting bundles carry the starter as executable part instead of an entry.
+    if format != BundleFormat::SelfExtracting {
+        let starter_name = match starter.extension() {
+            Some(ext) if ext.eq_ignore_ascii_case("exe") => format!("{}.exe", STARTER_NAME),
+            _ => STARTER_NAME.to_owned(),
+        };
+
+        entries.insert(starter_name, BundleEntry::file(starter.to_path_buf(), EXECUTABLE_MODE));
+    }
+
+    if let Some(jar) = &options.jar {
+        entries.insert(APP_JAR_NAME.to_owned(), BundleEntry::file(jar.clone(), FILE_MODE));
+
+        let signature = signature_path(jar);
+        if signature.is_file() {
+            entries.insert(APP_JAR_SIGNATURE_NAME.to_owned(), BundleEntry::file(signature, FILE_MODE));
+        }
+    }
+
+    collect_dir(&options.runtime, RUNTIME_DIR_NAME, keep_links, &mut entries).map_err(|e| format!("Collect runtime: {}", e))?;
+
+    if let Some(icons) = &options.icons {
+        collect_dir(icons, ICONS_DIR_NAME, keep_links, &mut entries).map_err(|e| format!("Collect icons: {}", e))?;
+    }
+
+    if let Some(lib) = &options.lib {
+        collect_dir(lib, APP_LIB_DIR_NAME, keep_links, &mut entries).map_err(|e| format!("Collect libraries: {}", e))?;
+    }
+
+    Ok(entries)
+}
+
+fn resolve_format(options: &BundleOptions) -> Result<BundleFormat, Box<dyn Error>> {
+    if let Some(format) = options.format {
+        return Ok(format);
+    }
+
+    let name = options
+        .output
+        .file_name()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .to_ascii_lowercase();
+    if name.ends_with(".zip") {
+        Ok(BundleFormat::Zip)
+    } else if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+        Ok(BundleFormat::TarGz)
+    } else if name.ends_with(".tar.zst") {
+        Ok(BundleFormat::TarZst)
+    } else {
+        Err(format!("Unknown bundle format of {}, specify --format", options.output.display()).into())
+    }
+}
+
+fn validate_inputs(options: &BundleOptions, starter: &Path) -> Result<(), Box<dyn Error>> {
+    if !starter.is_file() {
+        return Err(format!("Starter {} not found", starter.display()).into());
+    }
+
+    if let Some(jar) = &options.jar {
+        resolve_app_metadata(jar).map_err(|e| format!("Invalid app jar {}: {}", jar.display(), e))?;
+    }
+
+    let has_jvm = ["lib/server/libjvm.so", "lib/libjvm.so", "bin/server/jvm.dll"]
+        .iter()
+        .any(|path| options.runtime.join(path).is_file());
+    if !has_jvm {
+        return Err(format!("No JVM found in runtime image {}", options.runtime.display()).into());
+    }
+
+    Ok(())
+}
+
+// Links are left out, `verify_bundle` checks what they point to.
+fn hash_entries(entries: &BTreeMap<String, BundleEntry>) -> Result<BTreeMap<String, String>, Box<dyn Error>> {
+    let mut hashes = BTreeMap::new();
+
+    for (name, entry) in entries {
+        let hash = match &entry.content {
+            EntryContent::File(source) => hash_file(source).map_err(|e| format!("Hash {}: {}", source.display(), e))?,
+            EntryContent::Generated(content) => encode_hex(&Sha256::digest(content)),
+            EntryContent::Symlink(_) => continue,
+        };
+
+        hashes.insert(name.clone(), hash);
+    }
+
+    Ok(hashes)
+}
+
+fn write_zip(file: File, entries: &BTreeMap<String, BundleEntry>) -> Result<File, Box<dyn Error>> {
+    let mut zip = ZipWriter::new(file);
+
+    let options = FileOptions::default()
+        .compression_method(CompressionMethod::Deflated)
+        .last_modified_time(DateTime::default());
+
+    for (name, entry) in entries {
+        zip.start_file(name, options.unix_permissions(entry.mode))?;
+
+        match &entry.content {
+            EntryContent::File(source) => {
+                io::copy(&mut File::open(source)?, &mut zip)?;
+            }
+            EntryContent::Generated(content) => zip.write_all(content)?,
+            EntryContent::Symlink(_) => return Err(format!("Unexpected link {}", name).into()),
+        }
+    }
+
+    Ok(zip.finish()?)
+}
+
+fn write_tar<'a, W: Write>(
+    writer: W,
+    entries: impl IntoIterator<Item = (&'a String, &'a BundleEntry)>,
+) -> Result<W, Box<dyn Error>> {
+    let mut tar = tar::Builder::new(writer);
+
+    for (name, entry) in entries {
+        let mut header = tar::Header::new_gnu();
+        header.set_mode(entry.mode);
+        header.set_mtime(BUNDLE_MTIME);
+        header.set_uid(0);
+        header.set_gid(0);
+
+        match &entry.content {
+            EntryContent::File(source) => {
+                let file = File::open(source)?;
+
+                header.set_size(file.metadata()?.len());
+                tar.append_data(&mut header, name, file)?;
+            }
+            EntryContent::Generated(content) => {
+                header.set_size(content.len() as u64);
+                tar.append_data(&mut header, name, content.as_slice())?;
+            }
+            EntryContent::Symlink(target) => {
+                header.set_entry_type(tar::EntryType::Symlink);
+                header.set_size(0);
+                tar.append_link(&mut header, name, target)?;
+            }
+        }
+    }
+
+    Ok(tar.into_inner()?)
+}
+
+fn write_bundle(
+    output: &Path,
+    format: BundleFormat,
+    starter: &Path,
+    entries: &BTreeMap<String, BundleEntry>,
+    hashes: &BTreeMap<String, String>,
+) -> Result<(), Box<dyn Error>> {
+    let mut file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(output)?;
+
+    let file = match format {
+        BundleFormat::Zip => write_zip(file, entries)?,
+        BundleFormat::TarGz => {
+            let encoder = flate2::write::GzEncoder::new(file, flate2::Compression::best());
+
+            write_tar(encoder, entries)?.finish()?
+        }
+        BundleFormat::TarZst => write_tar(zstd::Encoder::new(file, ZSTD_LEVEL)?, entries)?.finish()?,
+        BundleFormat::SelfExtracting => {
+            io::copy(&mut File::open(starter)?, &mut file)?;
+
+            let offset = file.stream_position()?;
+            let archived = entries.iter().filter(|(name, _)| !EMBEDDED_ENTRIES.contains(&name.as_str()));
+            let mut file = write_tar(zstd::Encoder::new(file, ZSTD_LEVEL)?, archived)?.finish()?;
+
+            let length = file.stream_position()? - offset;
+            let mut index = vec![IndexEntry {
+                name: PAYLOAD_ARCHIVE_NAME.to_owned(),
+                offset: 0,
+                length,
+                hash: encode_hex(&hash_region(&mut file, offset, length)?),
+            }];
+            file.seek(SeekFrom::Start(offset + length))?;
+
+            for (name, entry) in entries.iter().filter(|(name, _)| EMBEDDED_ENTRIES.contains(&name.as_str())) {
+                let start = file.stream_position()? - offset;
+
+                match &entry.content {
+                    EntryContent::File(source) => io::copy(&mut File::open(source)?, &mut file)?,
+                    _ => return Err(format!("Unexpected embedded entry {}", name).into()),
+                };
+
+                index.push(IndexEntry {
+                    name: name.clone(),
+                    offset: start,
+                    length: file.stream_position()? - offset - start,
+                    hash: hashes[name].clone(),
+                });
+            }
+
+            write_index(&mut file, &index)?;
+
+            let length = file.stream_position()? - offset;
+            let hash = hash_region(&mut file, offset, length)?;
+            file.seek(SeekFrom::Start(offset + length))?;
+            write_trailer(&mut file, &hash, offset)?;
+
+            file
+        }
+    };
+
+    file.sync_all()?;
+
+    Ok(())
+}
+
+fn read_zip(file: File) -> Result<BTreeMap<String, ArchivedEntry>, Box<dyn Error>> {
+    let mut zip = ZipArchive::new(file)?;
+    let mut archived = BTreeMap::new();
+
+    for idx in 0..zip.len() {
+        let mut file = zip.by_index(idx)?;
+        let mode = file.unix_mode().unwrap_or_default() & 0o777;
+
+        let mut hasher = Sha256::new();
+        io::copy(&mut file, &mut hasher)?;
+
+        archived.insert(
+            file.name().to_owned(),
+            ArchivedEntry {
+                mode,
+                content: ArchivedContent::Hash(encode_hex(&hasher.finalize())),
+            },
+        );
+    }
+
+    Ok(archived)
+}
+
+fn read_tar<R: Read>(reader: R) -> Result<BTreeMap<String, ArchivedEntry>, Box<dyn Error>> {
+    let mut tar = tar::Archive::new(reader);
+    let mut archived = BTreeMap::new();
+
+    for entry in tar.entries()? {
+        let mut entry = entry?;
+        let name = entry.path()?.to_string_lossy().into_owned();
+        let mode = entry.header().mode()? & 0o777;
+
+        let content = match entry.header().entry_type() {
+            tar::EntryType::Symlink => ArchivedContent::Link(entry.link_name()?.ok_or("Link without target")?.into_owned()),
+            _ => {
+                let mut hasher = Sha256::new();
+                io::copy(&mut entry, &mut hasher)?;
+
+                ArchivedContent::Hash(encode_hex(&hasher.finalize()))
+            }
+        };
+
+        archived.insert(name, ArchivedEntry { mode, content });
+    }
+
+    Ok(archived)
+}
+
+fn read_self_extracting(mut file: File, starter: &Path) -> Result<BTreeMap<String, ArchivedEntry>, Box<dyn Error>> {
+    let trailer = read_trailer(&mut file)?.ok_or("Payload trailer missing")?;
+
+    if hash_region(&mut file, trailer.offset, trailer.length)? != trailer.hash {
+        return Err("Payload hash mismatch".into());
+    }
+
+    if encode_hex(&hash_region(&mut file, 0, trailer.offset)?) != hash_file(starter)? {
+        return Err("Starter content mismatch".into());
+    }
+
+    let mut archived = BTreeMap::new();
+
+    for entry in read_index(&mut file, &trailer)? {
+        let hash = encode_hex(&hash_region(&mut file, trailer.offset + entry.offset, entry.length)?);
+        if hash != entry.hash {
+            return Err(format!("Index hash mismatch: {}", entry.name).into());
+        }
+
+        if entry.name == PAYLOAD_ARCHIVE_NAME {
+            file.seek(SeekFrom::Start(trailer.offset + entry.offset))?;
+
+            archived.append(&mut read_tar(zstd::Decoder::new((&mut file).take(entry.length))?)?);
+        } else {
+            archived.insert(
+                entry.name,
+                ArchivedEntry {
+                    mode: FILE_MODE,
+                    content: ArchivedContent::Hash(hash),
+                },
+            );
+        }
+    }
+
+    Ok(archived)
+}
+
+fn validate_bundle(
+    output: &Path,
+    format: BundleFormat,
+    starter: &Path,
+    entries: &BTreeMap<String, BundleEntry>,
+    hashes: &BTreeMap<String, String>,
+) -> Result<(), Box<dyn Error>> {
+    let file = File::open(output)?;
+
+    let archived = match format {
+        BundleFormat::Zip => read_zip(file)?,
+        BundleFormat::TarGz => read_tar(flate2::read::GzDecoder::new(file))?,
+        BundleFormat::TarZst => read_tar(zstd::Decoder::new(file)?)?,
+        BundleFormat::SelfExtracting => read_self_extracting(file, starter)?,
+    };
+
+    // Signature must match the jar, otherwise every launch with a pinned key is refused.
+    if let Some(EntryContent::File(jar)) = entries.get(APP_JAR_NAME).map(|entry| &entry.content) {
+        verify_signatures(&[jar])?;
+    }
+
+    if let Some(name) = archived.keys().find(|name| !entries.contains_key(*name)) {
+        return Err(format!("Unexpected entry {}", name).into());
+    }
+
+    for (name, entry) in entries {
+        let expected = ArchivedEntry {
+            mode: entry.mode,
+            content: match &entry.content {
+                EntryContent::Symlink(target) => ArchivedContent::Link(target.clone()),
+                _ => ArchivedContent::Hash(hashes[name].clone()),
+            },
+        };
+
+        match archived.get(name) {
+            None => return Err(format!("Missing entry {}", name).into()),
+            Some(archived) if archived.mode != expected.mode => return Err(format!("Mode mismatch: {}", name).into()),
+            Some(archived) if *archived != expected => return Err(format!("Content mismatch: {}", name).into()),
+            Some(_) => {}
+        }
+    }
+
+    Ok(())
+}
+
+pub fn create_bundle(options: &BundleOptions) -> Result<(), Box<dyn Error>> {
+    let starter = match &options.starter {
+        Some(starter) => starter.clone(),
+        None => std::env::current_exe()?,
+    };
+    let format = resolve_format(options)?;
+
+    validate_inputs(options, &starter).map_err(|e| format!("Validate inputs: {}", e))?;
+
+    let mut entries = collect_entries(options, format, &starter)?;
+    let mut hashes = hash_entries(&entries)?;
+
+    // Embedded entries of self-extracting bundles never land in the app directory.
+    let manifest = hashes
+        .iter()
+        .filter(|(name, _)| format != BundleFormat::SelfExtracting || !EMBEDDED_ENTRIES.contains(&name.as_str()))
+        .map(|(name, hash)| format!("{}  {}\n", hash, name))
+        .collect::<String>();
+    hashes.insert(
+        BUNDLE_HASHES_NAME.to_owned(),
+        encode_hex(&Sha256::digest(manifest.as_bytes())),
+    );
+    entries.insert(
+        BUNDLE_HASHES_NAME.to_owned(),
+        BundleEntry {
+            content: EntryContent::Generated(manifest.into_bytes()),
+            mode: FILE_MODE,
+        },
+    );
+
+    let mut partial = options.output.clone().into_os_string();
+    partial.push(".part");
+    let partial = PathBuf::from(partial);
+
+    let result = write_bundle(&partial, format, &starter, &entries, &hashes)
+        .map_err(|e| format!("Write bundle: {}", e))
+        .and_then(|_| {
+            validate_bundle(&partial, format, &starter, &entries, &hashes).map_err(|e| format!("Validate bundle: {}", e))
+        });
+    if let Err(err) = result {
+        let _ = std::fs::remove_file(&partial);
+
+        return Err(err.into());
+    }
+
+    #[cfg(unix)]
+    if format == BundleFormat::SelfExtracting {
+        use std::os::unix::fs::PermissionsExt;
+
+        std::fs::set_permissions(&partial, std::fs::Permissions::from_mode(EXECUTABLE_MODE))?;
+    }
+
+    std::fs::rename(&partial, &options.output)?;
+
+    _ = io::stderr().write_fmt(format_args!(
+        "[Starter] files={} output={} | Bundle created\n",
+        entries.len(),
+        options.output.display()
+    ));
+
+    Ok(())
+}
@@ -0,0 +1,100 @@
+use std::{
+    error::Error,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    integrity::{bundle_name, listed_files},
+    metadata::Metadata,
+    utils::strings::decode_percent,
+    vmoptions::VMOptions,
+    APP_LIB_DIR_NAME,
+};
+
+const ALL_UNNAMED: &str = "ALL-UNNAMED";
+
+fn resolve_manifest_class_path(base_dir: &Path, metadata: &Metadata) -> Vec<PathBuf> {
+    metadata
+        .class_path
+        .iter()
+        .filter(|url| !url.contains(':'))
+        .filter_map(|url| decode_percent(url, false))
+        .map(|path| base_dir.join(path))
+        .filter(|path| path.exists())
+        .collect()
+}
+
+fn list_lib_jars(lib_dir: &Path) -> Result<Vec<PathBuf>, Box<dyn Error>> {
+    if !lib_dir.is_dir() {
+        return Ok(Vec::new());
+    }
+
+    let mut jars = std::fs::read_dir(lib_dir)?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.is_file() && path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("jar")))
+        .collect::<Vec<_>>();
+
+    jars.sort();
+
+    Ok(jars)
+}
+
+// Relative entries resolve against install root, main jar may be served from elsewhere.
+// Jars in lib directory are only picked up if hash list names them, so nothing unverified gets loaded.
+pub fn resolve_classpath(main_jar: &Path, root: &Path, metadata: &Metadata) -> Result<Vec<PathBuf>, Box<dyn Error>> {
+    let mut classpath = vec![main_jar.to_path_buf()];
+
+    let listed = listed_files(root)?;
+    let lib_jars = list_lib_jars(&root.join(APP_LIB_DIR_NAME))?
+        .into_iter()
+        .filter(|jar| match &listed {
+            Some(listed) => bundle_name(root, jar).is_some_and(|name| listed.contains(&name)),
+            None => true,
+        });
+
+    classpath.extend(resolve_manifest_class_path(root, metadata));
+    classpath.extend(lib_jars);
+
+    let mut resolved: Vec<PathBuf> = Vec::with_capacity(classpath.len());
+    for path in classpath {
+        if !resolved.contains(&path) {
+            resolved.push(path);
+        }
+    }
+
+    Ok(resolved)
+}
+
+fn with_default_target(entry: &str, target: &str) -> String {
+    if entry.contains('=') {
+        entry.to_owned()
+    } else {
+        format!("{}={}", entry, target)
+    }
+}
+
+pub fn configure_launch_mode(vm_options: &mut VMOptions, paths: &[PathBuf], metadata: &Metadata) {
+    let target = match &metadata.main_module {
+        Some(main_module) => {
+            vm_options
+                .path_list("--module-path=", paths)
+                .property("jdk.module.main", main_module);
+
+            main_module.as_str()
+        }
+        None => {
+            vm_options.path_list("-Djava.class.path=", paths);
+
+            ALL_UNNAMED
+        }
+    };
+
+    for entry in &metadata.add_opens {
+        vm_options.flag(&format!("--add-opens={}", with_default_target(entry, target)));
+    }
+
+    for entry in &metadata.add_exports {
+        vm_options.flag(&format!("--add-exports={}", with_default_target(entry, target)));
+    }
+}
@@ -0,0 +1,252 @@
+use std::error::Error;
+
+use cstr::cstr;
+use jni_sys::{jobject, JNIEnv};
+
+use crate::utils::{
+    java::{jcall, JStringExt},
+    strings::decode_percent,
+};
+
+pub const URL_SCHEME: &str = "clash";
+
+#[derive(Clone)]
+pub enum DeepLink {
+    InstallConfig { url: String, name: Option<String> },
+}
+
+fn validate_remote_url(url: &str) -> Result<(), Box<dyn Error>> {
+    let (scheme, rest) = url.split_once("://").ok_or("Invalid config url")?;
+    if !scheme.eq_ignore_ascii_case("http") && !scheme.eq_ignore_ascii_case("https") {
+        return Err(format!("Unsupported config url scheme {}", scheme).into());
+    }
+
+    let host = rest.split(['/', '?', '#']).next().unwrap_or_default();
+    if host.is_empty() || host.contains(char::is_whitespace) {
+        return Err("Invalid config url host".into());
+    }
+
+    Ok(())
+}
+
+impl DeepLink {
+    // Accepts `clash://<action>?<query>`, parameters are percent decoded and unknown ones ignored.
+    pub fn parse(link: &str) -> Result<Self, Box<dyn Error>> {
+        let rest = link
+            .split_once("://")
+            .filter(|(scheme, _)| scheme.eq_ignore_ascii_case(URL_SCHEME))
+            .map(|(_, rest)| rest)
+            .ok_or_else(|| format!("Unsupported link {}", link))?;
+
+        let rest = rest.split('#').next().unwrap_or_default();
+        let (action, query) = rest.split_once('?').unwrap_or((rest, ""));
+
+        let mut parameters = Vec::new();
+        for pair in query.split('&').filter(|pair| !pair.is_empty()) {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            let value = decode_percent(value, true).ok_or_else(|| format!("Invalid value of parameter {}", key))?;
+
+            parameters.push((key, value));
+        }
+        let parameter = |key: &str| parameters.iter().find(|(k, _)| *k == key).map(|(_, v)| v.clone());
+
+        match action.trim_end_matches('/') {
+            "install-config" => {
+                let url = parameter("url").ok_or("Missing parameter url")?;
+                validate_remote_url(&url)?;
+
+                Ok(DeepLink::InstallConfig {
+                    url,
+                    name: parameter("name").filter(|name| !name.is_empty()),
+                })
+            }
+            action => Err(format!("Unsupported action {}", action).into()),
+        }
+    }
+
+    pub fn fields(&self) -> Vec<(&'static str, String)> {
+        match self {
+            DeepLink::InstallConfig { url, name } => {
+                let mut fields = vec![("action", "install-config".to_owned()), ("url", url.clone())];
+                if let Some(name) = name {
+                    fields.push(("name", name.clone()));
+                }
+
+                fields
+            }
+        }
+    }
+
+    // Canonical form, parses back to the same link.
+    pub fn to_link(&self) -> String {
+        let mut fields = self.fields().into_iter();
+        let (_, action) = fields.next().unwrap();
+
+        let query = fields
+            .map(|(key, value)| format!("{}={}", key, encode_percent(&value)))
+            .collect::<Vec<_>>()
+            .join("&");
+
+        format!("{}://{}?{}", URL_SCHEME, action, query)
+    }
+}
+
+fn encode_percent(value: &str) -> String {
+    value
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => (b as char).to_string(),
+            b => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+// Exposed to app as `java.util.Map<String, String>` with an `action` key, so new actions need no new fields.
+pub fn new_java_action_map(env: *mut JNIEnv, fields: &[(&str, String)]) -> jobject {
+    let class = jcall!(env, FindClass, cstr!("java/util/HashMap").as_ptr());
+    let constructor = jcall!(env, GetMethodID, class, cstr!("<init>").as_ptr(), cstr!("()V").as_ptr());
+    let put = jcall!(
+        env,
+        GetMethodID,
+        class,
+        cstr!("put").as_ptr(),
+        cstr!("(Ljava/lang/Object;Ljava/lang/Object;)Ljava/lang/Object;").as_ptr()
+    );
+
+    let map = jcall!(env, NewObject, class, constructor);
+    for (key, value) in fields {
+        let key = key.to_string().to_java_string(env);
+        let value = value.to_java_string(env);
+
+        jcall!(env, CallObjectMethod, map, put, key, value);
+        jcall!(env, DeleteLocalRef, key);
+        jcall!(env, DeleteLocalRef, value);
+    }
+
+    map
+}
+
+#[cfg(test)]
+mod tests {
+    use super::DeepLink;
+
+    fn parse_fields(link: &str) -> Vec<(&'static str, String)> {
+        DeepLink::parse(link).unwrap().fields()
+    }
+
+    fn parse_error(link: &str) -> String {
+        DeepLink::parse(link).err().unwrap().to_string()
+    }
+
+    #[test]
+    fn decodes_percent_and_plus() {
+        let fields = parse_fields(
+            "clash://install-config?url=https%3A%2F%2Fexample.com%2Fsub%3Ftoken%3Da%2Bb&name=My+Config%20%E9%85%8D%E7%BD%AE",
+        );
+
+        assert_eq!(
+            fields,
+            [
+                ("action", "install-config".to_owned()),
+                ("url", "https://example.com/sub?token=a+b".to_owned()),
+                ("name", "My Config 配置".to_owned()),
+            ]
+        );
+    }
+
+    #[test]
+    fn ignores_fragment_unknown_parameters_and_empty_name() {
+        let fields = parse_fields("CLASH://install-config/?x=1&url=http://example.com&name=#name=ignored");
+
+        assert_eq!(
+            fields,
+            [
+                ("action", "install-config".to_owned()),
+                ("url", "http://example.com".to_owned())
+            ]
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_encoding() {
+        for link in [
+            "clash://install-config?url=https://example.com/%zz",
+            "clash://install-config?url=https://example.com/%2",
+            "clash://install-config?url=https://example.com/%E9%85",
+        ] {
+            assert_eq!(parse_error(link), "Invalid value of parameter url", "{}", link);
+        }
+    }
+
+    #[test]
+    fn rejects_missing_or_invalid_url() {
+        for (link, error) in [
+            ("clash://install-config", "Missing parameter url"),
+            ("clash://install-config?name=a", "Missing parameter url"),
+            ("clash://install-config?url=", "Invalid config url"),
+            ("clash://install-config?url=example.com", "Invalid config url"),
+            ("clash://install-config?url=https://", "Invalid config url host"),
+            ("clash://install-config?url=https:///path", "Invalid config url host"),
+            ("clash://install-config?url=https://exa%20mple.com", "Invalid config url host"),
+        ] {
+            assert_eq!(parse_error(link), error, "{}", link);
+        }
+    }
+
+    #[test]
+    fn rejects_other_url_schemes() {
+        for (link, error) in [
+            (
+                "clash://install-config?url=file:///etc/passwd",
+                "Unsupported config url scheme file",
+            ),
+            (
+                "clash://install-config?url=ftp://example.com/a",
+                "Unsupported config url scheme ftp",
+            ),
+            ("clash://install-config?url=javascript:alert(1)", "Invalid config url"),
+            ("clash://install-config?url=data:text/plain,a", "Invalid config url"),
+        ] {
+            assert_eq!(parse_error(link), error, "{}", link);
+        }
+    }
+
+    #[test]
+    fn rejects_other_links_and_actions() {
+        for (link, error) in [
+            (
+                "https://install-config?url=https://example.com",
+                "Unsupported link https://install-config?url=https://example.com",
+            ),
+            (
+                "clash:install-config?url=https://example.com",
+                "Unsupported link clash:install-config?url=https://example.com",
+            ),
+            (
+                "clash://remove-config?url=https://example.com",
+                "Unsupported action remove-config",
+            ),
+            ("clash://?url=https://example.com", "Unsupported action "),
+        ] {
+            assert_eq!(parse_error(link), error, "{}", link);
+        }
+    }
+
+    #[test]
+    fn canonical_link_parses_back() {
+        for link in [
+            "clash://install-config?url=https://example.com/sub?a=1%26b=2%23frag&name=a+%2B+b%3D%26%20%E9%85%8D%E7%BD%AE",
+            "clash://install-config?url=http://example.com",
+        ] {
+            let parsed = DeepLink::parse(link).unwrap();
+            let canonical = parsed.to_link();
+
+            // Instance messages are single lines split at the first space.
+            assert!(!canonical.contains(char::is_whitespace), "{}", canonical);
+
+            let reparsed = DeepLink::parse(&canonical).unwrap();
+            assert_eq!(reparsed.fields(), parsed.fields());
+            assert_eq!(reparsed.to_link(), canonical);
+        }
+    }
+}
@@ -0,0 +1,167 @@
+use std::error::Error;
+
+use crate::{
+    layout::InstallLayout,
+    metadata::Metadata,
+    options::{AutostartOptions, Options},
+};
+
+pub const ICONS_DIR_NAME: &str = "icons";
+
+// Launching from the menu keeps the location choices made when installing.
+#[cfg(target_os = "linux")]
+fn starter_command(options: &Options) -> Result<Vec<String>, Box<dyn Error>> {
+    let starter = std::env::current_exe()?;
+    let mut command = vec![starter.to_str().ok_or("Starter path is not valid UTF-8")?.to_owned()];
+
+    if options.portable {
+        command.push("--portable".to_owned());
+    }
+
+    if let Some(profile) = &options.profile {
+        command.push("--profile".to_owned());
+        command.push(profile.clone());
+    }
+
+    if !options.base_directory.is_empty() {
+        command.push("--base-directory".to_owned());
+        command.push(options.base_directory.clone());
+    }
+
+    Ok(command)
+}
+
+// Entries of other profiles are told apart by name in menus and session settings.
+#[cfg(target_os = "linux")]
+fn entry_name(options: &Options, metadata: &Metadata) -> String {
+    match options.profile_name() {
+        crate::dirs::DEFAULT_PROFILE_NAME => metadata.name.clone(),
+        profile => format!("{} ({})", metadata.name, profile),
+    }
+}
+
+pub fn install_desktop_entry(options: &Options, layout: &InstallLayout, metadata: &Metadata) -> Result<(), Box<dyn Error>> {
+    #[cfg(target_os = "linux")]
+    {
+        use std::io::Write;
+
+        let id = crate::dirs::desktop_entry_id(&metadata.edition, options.profile_name());
+        let exec = starter_command(options)?;
+
+        let path = crate::linux::desktop::install_desktop_entry(&crate::linux::desktop::DesktopEntry {
+            id: &id,
+            name: &entry_name(options, metadata),
+            exec: &exec,
+            url_scheme: crate::deeplink::URL_SCHEME,
+            icons_dir: &layout.root.join(ICONS_DIR_NAME),
+        })?;
+
+        _ = std::io::stderr().write_fmt(format_args!("[Starter] path={} | Desktop entry installed\n", path.display()));
+
+        Ok(())
+    }
+
+    #[cfg(windows)]
+    {
+        let _ = (options, layout, metadata);
+
+        Err("Desktop entry is only supported on Linux".into())
+    }
+}
+
+pub fn uninstall_desktop_entry(options: &Options, metadata: &Metadata) -> Result<(), Box<dyn Error>> {
+    #[cfg(target_os = "linux")]
+    {
+        use std::io::Write;
+
+        let id = crate::dirs::desktop_entry_id(&metadata.edition, options.profile_name());
+        let path = crate::linux::desktop::uninstall_desktop_entry(&id)?;
+
+        _ = std::io::stderr().write_fmt(format_args!(
+            "[Starter] path={} | Desktop entry uninstalled\n",
+            path.display()
+        ));
+
+        Ok(())
+    }
+
+    #[cfg(windows)]
+    {
+        let _ = (options, metadata);
+
+        Err("Desktop entry is only supported on Linux".into())
+    }
+}
+
+pub fn manage_autostart(options: &Options, autostart: &AutostartOptions, metadata: &Metadata) -> Result<(), Box<dyn Error>> {
+    #[cfg(target_os = "linux")]
+    {
+        use std::io::Write;
+
+        use crate::{
+            linux::autostart,
+            options::{AutostartAction, AutostartMethod},
+        };
+
+        let id = crate::dirs::desktop_entry_id(&metadata.edition, options.profile_name());
+        let method = autostart.method.unwrap_or(AutostartMethod::Desktop);
+        let method_name = |method: AutostartMethod| match method {
+            AutostartMethod::Desktop => "desktop",
+            AutostartMethod::Systemd => "systemd",
+        };
+
+        match autostart.action {
+            AutostartAction::Enable => {
+                // Units run outside of graphical sessions as a headless supervisor.
+                let mut exec = starter_command(options)?;
+                exec.push(match method {
+                    AutostartMethod::Desktop => "--hide-window".to_owned(),
+                    AutostartMethod::Systemd => "--headless".to_owned(),
+                });
+
+                let path = autostart::enable_autostart(method, &id, &entry_name(options, metadata), &exec)?;
+
+                _ = std::io::stderr().write_fmt(format_args!(
+                    "[Starter] method={} path={} | Autostart enabled\n",
+                    method_name(method),
+                    path.display()
+                ));
+            }
+            AutostartAction::Disable => {
+                let path = autostart::disable_autostart(method, &id)?;
+
+                _ = std::io::stderr().write_fmt(format_args!(
+                    "[Starter] method={} path={} | Autostart disabled\n",
+                    method_name(method),
+                    path.display()
+                ));
+            }
+            AutostartAction::Status => {
+                let methods = match autostart.method {
+                    Some(method) => vec![method],
+                    None => vec![AutostartMethod::Desktop, AutostartMethod::Systemd],
+                };
+
+                for method in methods {
+                    let (enabled, path) = autostart::is_autostart_enabled(method, &id)?;
+
+                    _ = std::io::stdout().write_fmt(format_args!(
+                        "method={} enabled={} path={}\n",
+                        method_name(method),
+                        enabled,
+                        path.display()
+                    ));
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    #[cfg(windows)]
+    {
+        let _ = (options, autostart, metadata);
+
+        Err("Autostart is only supported on Linux".into())
+    }
+}
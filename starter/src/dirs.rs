@@ -1,9 +1,63 @@
-use std::{error::Error, path::PathBuf};
+use std::{
+    error::Error,
+    path::{Path, PathBuf},
+};
 
-use crate::metadata::Metadata;
+use crate::{
+    metadata::{Edition, Metadata},
+    options::Options,
+};
 
 const BASE_DIR_FOSS: &str = "clash-multiplatform-foss";
 const BASE_DIR_PREMIUM: &str = "clash-multiplatform";
+const PORTABLE_MARKER_NAME: &str = "portable";
+const PORTABLE_BASE_DIR_NAME: &str = "data";
+const PORTABLE_CACHE_DIR_NAME: &str = "cache";
+const PROFILE_SEPARATOR: &str = "@";
+const ENTRY_PROFILE_SEPARATOR: &str = "-profile-";
+const MAX_PROFILE_NAME_LENGTH: usize = 64;
+pub const DEFAULT_PROFILE_NAME: &str = "default";
+
+pub fn base_dir_name(edition: &Edition) -> String {
+    match edition {
+        Edition::Foss => BASE_DIR_FOSS.to_owned(),
+        Edition::Premium => BASE_DIR_PREMIUM.to_owned(),
+        Edition::Other(name) => format!("{}-{}", BASE_DIR_PREMIUM, name.to_ascii_lowercase()),
+    }
+}
+
+// Desktop entries and autostart units of other profiles are installed side by side, e.g. `clash-multiplatform-profile-work`.
+// `@` would make systemd take the unit for a template instance.
+pub fn desktop_entry_id(edition: &Edition, profile: &str) -> String {
+    if profile == DEFAULT_PROFILE_NAME {
+        base_dir_name(edition)
+    } else {
+        format!("{}{}{}", base_dir_name(edition), ENTRY_PROFILE_SEPARATOR, profile)
+    }
+}
+
+// Portable installs keep everything in the directory of the executable.
+pub fn resolve_portable_dir(options: &Options, app_dir: &Path) -> Option<PathBuf> {
+    if options.portable || app_dir.join(PORTABLE_MARKER_NAME).exists() {
+        Some(app_dir.to_path_buf())
+    } else {
+        None
+    }
+}
+
+pub fn default_cache_dir(portable_dir: Option<&Path>, edition: &Edition) -> Result<PathBuf, Box<dyn Error>> {
+    if let Some(portable_dir) = portable_dir {
+        return Ok(portable_dir.join(PORTABLE_CACHE_DIR_NAME));
+    }
+
+    #[cfg(windows)]
+    let cache_dir = crate::win32::dirs::current_user_local_directory()?;
+
+    #[cfg(target_os = "linux")]
+    let cache_dir = crate::linux::dirs::current_user_cache_directory()?;
+
+    Ok(cache_dir.join(base_dir_name(edition)))
+}
 
 pub fn current_app_dir() -> Result<PathBuf, Box<dyn Error>> {
     if let Some(path) = std::env::current_exe()?.parent() {
@@ -13,16 +67,155 @@ pub fn current_app_dir() -> Result<PathBuf, Box<dyn Error>> {
     }
 }
 
-pub fn default_base_dir(metadata: &Metadata) -> Result<PathBuf, Box<dyn Error>> {
+pub struct AppDirs {
+    pub config: PathBuf,
+    pub data: PathBuf,
+    pub state: PathBuf,
+    pub cache: PathBuf,
+}
+
+impl AppDirs {
+    fn single(dir: PathBuf, cache: PathBuf) -> AppDirs {
+        AppDirs {
+            config: dir.clone(),
+            data: dir.clone(),
+            state: dir,
+            cache,
+        }
+    }
+
+    // Profiles live next to the default directories as `<name>@<profile>`.
+    fn with_profile(self, profile: &str) -> AppDirs {
+        let apply = |dir: PathBuf| {
+            let mut name = dir.file_name().unwrap_or_default().to_os_string();
+            name.push(PROFILE_SEPARATOR);
+            name.push(profile);
+
+            dir.with_file_name(name)
+        };
+
+        AppDirs {
+            config: apply(self.config),
+            data: apply(self.data),
+            state: apply(self.state),
+            cache: apply(self.cache),
+        }
+    }
+
+    // Same directory shows up more than once if locations are not split.
+    pub fn unique(&self) -> Vec<&Path> {
+        let mut dirs: Vec<&Path> = Vec::new();
+        for dir in [&self.config, &self.data, &self.state, &self.cache] {
+            if !dirs.contains(&dir.as_path()) {
+                dirs.push(dir);
+            }
+        }
+
+        dirs
+    }
+}
+
+pub fn validate_profile_name(name: &str) -> Result<(), Box<dyn Error>> {
+    let valid = !name.is_empty()
+        && name.len() <= MAX_PROFILE_NAME_LENGTH
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+
+    if valid {
+        Ok(())
+    } else {
+        Err(format!("Invalid profile name {}, only letters, digits, '-' and '_' allowed", name).into())
+    }
+}
+
+#[cfg(windows)]
+fn default_app_dirs(metadata: &Metadata) -> Result<AppDirs, Box<dyn Error>> {
+    let base_dir = crate::win32::dirs::current_user_local_directory()?.join(base_dir_name(&metadata.edition));
+
+    Ok(AppDirs::single(base_dir.clone(), base_dir))
+}
+
+#[cfg(target_os = "linux")]
+fn default_app_dirs(metadata: &Metadata) -> Result<AppDirs, Box<dyn Error>> {
+    let name = base_dir_name(&metadata.edition);
+
+    Ok(AppDirs {
+        config: crate::linux::dirs::current_user_config_directory()?.join(&name),
+        data: crate::linux::dirs::current_user_data_directory()?.join(&name),
+        state: crate::linux::dirs::current_user_state_directory()?.join(&name),
+        cache: crate::linux::dirs::current_user_cache_directory()?.join(&name),
+    })
+}
+
+// Explicit base directory and portable mode keep everything in one place.
+pub fn resolve_profile_dirs(
+    options: &Options,
+    portable_dir: Option<&Path>,
+    metadata: &Metadata,
+    profile: &str,
+) -> Result<AppDirs, Box<dyn Error>> {
+    validate_profile_name(profile)?;
+
+    let dirs = if !options.base_directory.is_empty() {
+        let base_dir = PathBuf::from(&options.base_directory);
+
+        AppDirs::single(base_dir.clone(), base_dir)
+    } else if let Some(portable_dir) = portable_dir {
+        AppDirs::single(
+            portable_dir.join(PORTABLE_BASE_DIR_NAME),
+            portable_dir.join(PORTABLE_CACHE_DIR_NAME),
+        )
+    } else {
+        default_app_dirs(metadata)?
+    };
+
+    if profile == DEFAULT_PROFILE_NAME {
+        Ok(dirs)
+    } else {
+        Ok(dirs.with_profile(profile))
+    }
+}
+
+pub fn resolve_app_dirs(options: &Options, portable_dir: Option<&Path>, metadata: &Metadata) -> Result<AppDirs, Box<dyn Error>> {
+    resolve_profile_dirs(options, portable_dir, metadata, options.profile_name())
+}
+
+// Profiles are found by their config directory, other locations are created on first launch.
+pub fn list_profiles(default_dirs: &AppDirs) -> Vec<String> {
+    let (Some(parent), Some(prefix)) = (default_dirs.config.parent(), default_dirs.config.file_name()) else {
+        return Vec::new();
+    };
+    let mut prefix = prefix.to_os_string();
+    prefix.push(PROFILE_SEPARATOR);
+    let prefix = prefix.to_string_lossy().into_owned();
+
+    let mut profiles = std::fs::read_dir(parent)
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.path().is_dir())
+        .filter_map(|entry| entry.file_name().to_str()?.strip_prefix(&prefix).map(str::to_owned))
+        .filter(|name| validate_profile_name(name).is_ok())
+        .collect::<Vec<_>>();
+    profiles.sort();
+
+    profiles
+}
+
+// Where every release before the XDG split kept all data, `None` if the user chose the location.
+pub fn legacy_base_dir(
+    options: &Options,
+    portable_dir: Option<&Path>,
+    metadata: &Metadata,
+) -> Result<Option<PathBuf>, Box<dyn Error>> {
+    if !options.base_directory.is_empty() || portable_dir.is_some() || options.profile_name() != DEFAULT_PROFILE_NAME {
+        return Ok(None);
+    }
+
     #[cfg(windows)]
     let local_dir = crate::win32::dirs::current_user_local_directory()?;
 
     #[cfg(target_os = "linux")]
-    let local_dir = crate::linux::dirs::current_user_config_directory()?;
+    let local_dir = home::home_dir().ok_or("User home directory not found")?.join(".config");
 
-    if metadata.is_premium {
-        Ok(local_dir.join(BASE_DIR_PREMIUM))
-    } else {
-        Ok(local_dir.join(BASE_DIR_FOSS))
-    }
+    Ok(Some(local_dir.join(base_dir_name(&metadata.edition))))
 }
@@ -0,0 +1,125 @@
+use std::{error::Error, ffi::CString};
+
+use jni_sys::{jclass, jmethodID, JNIEnv, JNI_TRUE};
+
+use crate::{
+    metadata::Metadata,
+    startup::StartupParameters,
+    utils::java::{jcall, new_java_string_array},
+};
+
+const DEFAULT_MAIN_CLASS: &str = "com.github.kr328.clash.MainKt";
+const DEFAULT_ENTRY_METHOD: &str = "main";
+const DEFAULT_ENTRY_SIGNATURE: &str = "(Lcom/github/kr328/clash/StartupParameters;)V";
+const STANDARD_ENTRY_SIGNATURE: &str = "([Ljava/lang/String;)V";
+
+enum EntryArgument {
+    StartupParameters(CString),
+    StringArray,
+}
+
+pub struct EntryPoint {
+    class: CString,
+    method: CString,
+    signature: Option<CString>,
+}
+
+impl EntryArgument {
+    fn from_signature(signature: &str) -> Result<EntryArgument, Box<dyn Error>> {
+        if signature == STANDARD_ENTRY_SIGNATURE {
+            return Ok(EntryArgument::StringArray);
+        }
+
+        let class = signature
+            .strip_prefix("(L")
+            .and_then(|s| s.strip_suffix(";)V"))
+            .filter(|s| !s.contains(';'))
+            .ok_or_else(|| format!("Unsupported entry signature {}", signature))?;
+
+        Ok(EntryArgument::StartupParameters(CString::new(class)?))
+    }
+}
+
+impl EntryPoint {
+    pub fn new(metadata: &Metadata) -> Result<EntryPoint, Box<dyn Error>> {
+        let (class, signature) = match &metadata.main_class {
+            Some(class) => (class.as_str(), metadata.entry_signature.as_deref()),
+            None => (
+                DEFAULT_MAIN_CLASS,
+                Some(metadata.entry_signature.as_deref().unwrap_or(DEFAULT_ENTRY_SIGNATURE)),
+            ),
+        };
+        let method = metadata.entry_method.as_deref().unwrap_or(DEFAULT_ENTRY_METHOD);
+
+        Ok(EntryPoint {
+            class: CString::new(class.replace('.', "/"))?,
+            method: CString::new(method)?,
+            signature: signature.map(CString::new).transpose()?,
+        })
+    }
+
+    fn find_method(&self, env: *mut JNIEnv, class: jclass, signature: &str) -> Result<jmethodID, Box<dyn Error>> {
+        let signature = CString::new(signature)?;
+
+        let method = jcall!(env, GetStaticMethodID, class, self.method.as_ptr(), signature.as_ptr());
+        if method.is_null() {
+            return Err(format!(
+                "Method {}{} not found in {}",
+                self.method.to_string_lossy(),
+                signature.to_string_lossy(),
+                self.class.to_string_lossy()
+            )
+            .into());
+        }
+
+        Ok(method)
+    }
+
+    pub fn invoke(&self, env: *mut JNIEnv, parameters: &StartupParameters) -> Result<(), Box<dyn Error>> {
+        let class = jcall!(env, FindClass, self.class.as_ptr());
+        if class.is_null() {
+            jcall!(env, ExceptionDescribe);
+
+            return Err("Invalid application package".into());
+        }
+
+        let (method, argument) = if let Some(signature) = &self.signature {
+            let signature = signature.to_str()?;
+
+            let method = self.find_method(env, class, signature);
+            if method.is_err() {
+                jcall!(env, ExceptionDescribe);
+            }
+
+            (method?, EntryArgument::from_signature(signature)?)
+        } else {
+            match self.find_method(env, class, DEFAULT_ENTRY_SIGNATURE) {
+                Ok(method) => (method, EntryArgument::from_signature(DEFAULT_ENTRY_SIGNATURE)?),
+                Err(_) => {
+                    jcall!(env, ExceptionClear);
+
+                    let method = self.find_method(env, class, STANDARD_ENTRY_SIGNATURE);
+                    if method.is_err() {
+                        jcall!(env, ExceptionDescribe);
+                    }
+
+                    (method?, EntryArgument::StringArray)
+                }
+            }
+        };
+
+        let argument = match argument {
+            EntryArgument::StartupParameters(class_name) => parameters.new_java_object(env, &class_name)?,
+            EntryArgument::StringArray => new_java_string_array(env, &parameters.starter_arguments),
+        };
+
+        jcall!(env, CallStaticVoidMethod, class, method, argument);
+        if jcall!(env, ExceptionCheck) == JNI_TRUE {
+            jcall!(env, ExceptionDescribe);
+
+            return Err("Unexpected exception".into());
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,71 @@
+use std::{error::Error, fmt::Display, path::Path};
+
+use crate::{bundle::RUNTIME_DIR_NAME, options::Options};
+
+const DESKTOP_MODULE_NAME: &str = "java.desktop";
+pub const NO_DISPLAY_SERVER: &str = "none";
+
+pub enum HeadlessReason {
+    Requested,
+    NoDesktopModule,
+    NoDisplay,
+}
+
+pub struct DisplaySession {
+    pub server: &'static str,
+    pub usable: bool,
+    pub properties: Vec<(&'static str, String)>,
+}
+
+impl Display for HeadlessReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            HeadlessReason::Requested => f.write_str("requested"),
+            HeadlessReason::NoDesktopModule => f.write_str("no-desktop-module"),
+            HeadlessReason::NoDisplay => f.write_str("no-display"),
+        }
+    }
+}
+
+// `release` of jlink images lists included modules, e.g. `MODULES="java.base java.logging"`.
+fn runtime_modules(runtime_dir: &Path) -> Option<Vec<String>> {
+    let release = std::fs::read_to_string(runtime_dir.join("release")).ok()?;
+    let modules = release.lines().find_map(|line| line.strip_prefix("MODULES="))?;
+
+    Some(modules.trim_matches('"').split_whitespace().map(str::to_owned).collect())
+}
+
+pub fn detect_display_session() -> DisplaySession {
+    #[cfg(target_os = "linux")]
+    return crate::linux::display::detect_display_session();
+
+    #[cfg(windows)]
+    return DisplaySession {
+        server: "windows",
+        usable: true,
+        properties: Vec::new(),
+    };
+}
+
+// Runtimes without a `release` file are assumed to be complete.
+// Only a missing graphical session falls back to headless, a user of an unusable one expects a window.
+pub fn resolve_headless(
+    options: &Options,
+    root: &Path,
+    display: &DisplaySession,
+) -> Result<Option<HeadlessReason>, Box<dyn Error>> {
+    if options.headless {
+        return Ok(Some(HeadlessReason::Requested));
+    }
+
+    match runtime_modules(&root.join(RUNTIME_DIR_NAME)) {
+        Some(modules) if !modules.iter().any(|m| m == DESKTOP_MODULE_NAME) => Ok(Some(HeadlessReason::NoDesktopModule)),
+        _ if !display.usable && display.server == NO_DISPLAY_SERVER => Ok(Some(HeadlessReason::NoDisplay)),
+        _ if !display.usable => Err(format!(
+            "Display server {} is not supported without X11, enable XWayland or pass --headless",
+            display.server
+        )
+        .into()),
+        _ => Ok(None),
+    }
+}
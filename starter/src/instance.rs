@@ -0,0 +1,150 @@
+use std::{
+    collections::VecDeque,
+    error::Error,
+    io::{BufRead, BufReader, Read, Write},
+    path::Path,
+    sync::{Condvar, Mutex},
+};
+
+use sha2::{Digest, Sha256};
+
+use crate::{
+    deeplink::DeepLink,
+    utils::strings::{encode_hex, PathExt},
+};
+
+#[cfg_attr(windows, allow(dead_code))]
+const INSTANCE_LOCK_NAME: &str = "instance.lock";
+
+static MESSAGES: Mutex<VecDeque<InstanceMessage>> = Mutex::new(VecDeque::new());
+static MESSAGES_CONDVAR: Condvar = Condvar::new();
+
+pub enum InstanceMessage {
+    Activate,
+    Open(DeepLink),
+}
+
+impl InstanceMessage {
+    fn encode(&self) -> String {
+        match self {
+            InstanceMessage::Activate => "activate\n".to_owned(),
+            InstanceMessage::Open(link) => format!("open {}\n", link.to_link()),
+        }
+    }
+
+    fn decode(line: &str) -> Option<Self> {
+        match line.split_once(' ') {
+            None if line == "activate" => Some(InstanceMessage::Activate),
+            Some(("open", link)) => DeepLink::parse(link).ok().map(InstanceMessage::Open),
+            _ => None,
+        }
+    }
+
+    pub fn fields(&self) -> Vec<(&'static str, String)> {
+        match self {
+            InstanceMessage::Activate => vec![("action", "activate".to_owned())],
+            InstanceMessage::Open(link) => link.fields(),
+        }
+    }
+}
+
+fn push_message(message: InstanceMessage) {
+    MESSAGES.lock().unwrap().push_back(message);
+    MESSAGES_CONDVAR.notify_all();
+}
+
+fn receive_messages(stream: impl Read) {
+    for line in BufReader::new(stream).lines().map_while(Result::ok) {
+        if let Some(message) = InstanceMessage::decode(&line) {
+            push_message(message);
+        }
+    }
+}
+
+// Socket and pipe names are limited in length, a digest of state directory keeps them short.
+fn instance_id(state_dir: &Path) -> String {
+    let id = encode_hex(&Sha256::digest(state_dir.to_string_without_extend_length_mark()));
+
+    id[..16].to_owned()
+}
+
+pub fn wait_message() -> InstanceMessage {
+    let mut messages = MESSAGES.lock().unwrap();
+
+    loop {
+        if let Some(message) = messages.pop_front() {
+            return message;
+        }
+
+        messages = MESSAGES_CONDVAR.wait(messages).unwrap();
+    }
+}
+
+// Returns true if message was handed to a running instance and current one should exit.
+pub fn forward_or_listen(state_dir: &Path, message: &InstanceMessage) -> Result<bool, Box<dyn Error>> {
+    #[cfg(target_os = "linux")]
+    {
+        use crate::linux::instance::{open_instance_channel, private_socket_dir, InstanceChannel};
+
+        // Socket paths are limited to 108 bytes, runtime directory keeps them short.
+        let socket_dir = match std::env::var_os("XDG_RUNTIME_DIR")
+            .map(std::path::PathBuf::from)
+            .filter(|dir| dir.is_absolute() && dir.is_dir())
+        {
+            Some(dir) => dir,
+            None => private_socket_dir()?,
+        };
+        let socket_path = socket_dir.join(format!("clash-multiplatform-{}.sock", instance_id(state_dir)));
+
+        match open_instance_channel(&state_dir.join(INSTANCE_LOCK_NAME), &socket_path)? {
+            InstanceChannel::Primary(listener) => {
+                std::thread::spawn(move || {
+                    for stream in listener.incoming().flatten() {
+                        let _ = stream.set_read_timeout(Some(std::time::Duration::from_secs(5)));
+
+                        receive_messages(stream);
+                    }
+                });
+
+                Ok(false)
+            }
+            InstanceChannel::Secondary(mut stream) => {
+                stream.write_all(message.encode().as_bytes())?;
+
+                Ok(true)
+            }
+        }
+    }
+
+    #[cfg(windows)]
+    {
+        use crate::win32::instance::{open_instance_channel, InstanceChannel};
+
+        match open_instance_channel(&instance_id(state_dir))? {
+            InstanceChannel::Primary(listener) => {
+                std::thread::spawn(move || {
+                    while let Ok(pipe) = listener.accept() {
+                        receive_messages(pipe);
+                    }
+                });
+
+                Ok(false)
+            }
+            InstanceChannel::Secondary(mut pipe) => {
+                pipe.write_all(message.encode().as_bytes())?;
+
+                Ok(true)
+            }
+        }
+    }
+}
+
+pub fn is_instance_running(state_dir: &Path) -> Result<bool, Box<dyn Error>> {
+    #[cfg(target_os = "linux")]
+    return Ok(crate::linux::instance::is_instance_locked(
+        &state_dir.join(INSTANCE_LOCK_NAME),
+    )?);
+
+    #[cfg(windows)]
+    return Ok(crate::win32::instance::is_instance_locked(&instance_id(state_dir))?);
+}
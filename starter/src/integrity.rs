@@ -0,0 +1,256 @@
+use std::{
+    collections::{HashMap, HashSet},
+    error::Error,
+    fs::File,
+    io,
+    path::{Component, Path, PathBuf},
+    time::UNIX_EPOCH,
+};
+
+use sha2::{Digest, Sha256};
+
+use crate::{signature::SIGNATURE_SUFFIX, utils::strings::encode_hex};
+
+pub const BUNDLE_HASHES_NAME: &str = "bundle.sha256";
+
+struct ExpectedFile {
+    path: String,
+    hash: String,
+}
+
+struct FileStamp {
+    size: u64,
+    modified: u128,
+}
+
+enum Mismatch {
+    Missing(String),
+    Modified(String),
+}
+
+pub fn hash_file(path: &Path) -> io::Result<String> {
+    let mut hasher = Sha256::new();
+
+    io::copy(&mut File::open(path)?, &mut hasher)?;
+
+    Ok(encode_hex(&hasher.finalize()))
+}
+
+fn is_safe_relative_path(path: &str) -> bool {
+    Path::new(path).components().all(|c| matches!(c, Component::Normal(_)))
+}
+
+// Same format as `sha256sum`: "<hex hash> <space or asterisk><relative path>".
+fn parse_bundle_hashes(content: &str) -> Result<Vec<ExpectedFile>, Box<dyn Error>> {
+    let mut files = Vec::new();
+
+    for line in content.lines().map(|line| line.trim_end()).filter(|line| !line.is_empty()) {
+        let (hash, path) = line
+            .split_once(' ')
+            .map(|(hash, path)| (hash, path.strip_prefix(['*', ' ']).unwrap_or(path)))
+            .filter(|(hash, path)| hash.len() == 64 && hash.bytes().all(|c| c.is_ascii_hexdigit()) && !path.is_empty())
+            .ok_or_else(|| format!("Invalid bundle hash line: {}", line))?;
+
+        if !is_safe_relative_path(path) {
+            return Err(format!("Invalid bundle path: {}", path).into());
+        }
+
+        files.push(ExpectedFile {
+            path: path.to_owned(),
+            hash: hash.to_ascii_lowercase(),
+        });
+    }
+
+    Ok(files)
+}
+
+// Names use `/` like the hash list, paths outside of `root` have none.
+pub fn bundle_name(root: &Path, path: &Path) -> Option<String> {
+    let mut names = Vec::new();
+    for component in path.strip_prefix(root).ok()?.components() {
+        match component {
+            Component::Normal(name) => names.push(name.to_str()?),
+            _ => return None,
+        }
+    }
+
+    Some(names.join("/"))
+}
+
+// `None` if the bundle has no hash list.
+pub fn listed_files(app_dir: &Path) -> Result<Option<HashSet<String>>, Box<dyn Error>> {
+    let hashes_file = app_dir.join(BUNDLE_HASHES_NAME);
+    if !hashes_file.exists() {
+        return Ok(None);
+    }
+
+    let expected = parse_bundle_hashes(&std::fs::read_to_string(&hashes_file)?)?;
+
+    Ok(Some(expected.into_iter().map(|file| file.path).collect()))
+}
+
+fn stamp_of(path: &Path) -> Option<FileStamp> {
+    let metadata = std::fs::metadata(path).ok()?;
+    let modified = metadata.modified().ok()?.duration_since(UNIX_EPOCH).ok()?.as_nanos();
+
+    Some(FileStamp {
+        size: metadata.len(),
+        modified,
+    })
+}
+
+// Cache line format: "<size> <modified nanos> <hex hash> <relative path>".
+fn load_cache(cache_file: &Path) -> HashMap<String, (u64, u128, String)> {
+    let content = std::fs::read_to_string(cache_file).unwrap_or_default();
+
+    content
+        .lines()
+        .filter_map(|line| {
+            let mut segments = line.splitn(4, ' ');
+            let size = segments.next()?.parse().ok()?;
+            let modified = segments.next()?.parse().ok()?;
+            let hash = segments.next()?.to_owned();
+            let path = segments.next()?.to_owned();
+
+            Some((path, (size, modified, hash)))
+        })
+        .collect()
+}
+
+fn save_cache(cache_file: &Path, verified: &[(&ExpectedFile, FileStamp)]) -> io::Result<()> {
+    let content = verified
+        .iter()
+        .map(|(file, stamp)| format!("{} {} {} {}\n", stamp.size, stamp.modified, file.hash, file.path))
+        .collect::<String>();
+
+    std::fs::write(cache_file, content)
+}
+
+// Files in `optional` may be missing, e.g. the starter of versioned layout is moved into app directory on update.
+pub fn verify_bundle(app_dir: &Path, cache_file: Option<&Path>, optional: &[&str]) -> Result<(), Box<dyn Error>> {
+    let hashes_file = app_dir.join(BUNDLE_HASHES_NAME);
+    if !hashes_file.exists() {
+        return Ok(());
+    }
+
+    let expected = parse_bundle_hashes(&std::fs::read_to_string(&hashes_file)?)?;
+    let cache = cache_file.map(load_cache).unwrap_or_default();
+
+    let mut verified = Vec::with_capacity(expected.len());
+    let mut mismatches = Vec::new();
+
+    for file in &expected {
+        let path = app_dir.join(&file.path);
+
+        let stamp = match stamp_of(&path) {
+            Some(stamp) => stamp,
+            None if optional.contains(&file.path.as_str()) => continue,
+            None => {
+                mismatches.push(Mismatch::Missing(file.path.clone()));
+
+                continue;
+            }
+        };
+
+        let cached = cache
+            .get(&file.path)
+            .is_some_and(|(size, modified, hash)| *size == stamp.size && *modified == stamp.modified && *hash == file.hash);
+
+        if cached || hash_file(&path).is_ok_and(|hash| hash == file.hash) {
+            verified.push((file, stamp));
+        } else {
+            mismatches.push(Mismatch::Modified(file.path.clone()));
+        }
+    }
+
+    if let Some(cache_file) = cache_file {
+        if mismatches.is_empty() {
+            let _ = save_cache(cache_file, &verified);
+        } else {
+            let _ = std::fs::remove_file(cache_file);
+        }
+    }
+
+    if mismatches.is_empty() {
+        return Ok(());
+    }
+
+    let details = mismatches
+        .iter()
+        .map(|m| match m {
+            Mismatch::Missing(path) => format!("\n  missing: {}", path),
+            Mismatch::Modified(path) => format!("\n  corrupted: {}", path),
+        })
+        .collect::<String>();
+
+    Err(format!("Bundle corrupted, please reinstall.{}", details).into())
+}
+
+fn collect_unlisted(dir: &Path, prefix: &str, listed: &HashSet<&str>, unlisted: &mut Vec<String>) -> io::Result<()> {
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        let name = format!("{}{}", prefix, entry.file_name().to_string_lossy());
+        let file_type = entry.file_type()?;
+
+        if file_type.is_dir() {
+            collect_unlisted(&entry.path(), &format!("{}/", name), listed, unlisted)?;
+        } else if !file_type.is_symlink() && !listed.contains(name.as_str()) {
+            unlisted.push(name);
+        }
+    }
+
+    Ok(())
+}
+
+fn unlisted_error(unlisted: &[String]) -> Box<dyn Error> {
+    let details = unlisted
+        .iter()
+        .map(|path| format!("\n  unlisted: {}", path))
+        .collect::<String>();
+
+    format!("Bundle contains files without hash.{}", details).into()
+}
+
+// Update archives must be covered by the hash list completely, so nothing unverified gets installed.
+pub fn verify_complete_bundle(dir: &Path) -> Result<(), Box<dyn Error>> {
+    let hashes_file = dir.join(BUNDLE_HASHES_NAME);
+    if !hashes_file.is_file() {
+        return Err(format!("Bundle does not contain {}", BUNDLE_HASHES_NAME).into());
+    }
+
+    let expected = parse_bundle_hashes(&std::fs::read_to_string(&hashes_file)?)?;
+    let signature_name = format!("{}{}", BUNDLE_HASHES_NAME, SIGNATURE_SUFFIX);
+    let listed = expected
+        .iter()
+        .map(|file| file.path.as_str())
+        .chain([BUNDLE_HASHES_NAME, signature_name.as_str()])
+        .collect::<HashSet<_>>();
+
+    let mut unlisted = Vec::new();
+    collect_unlisted(dir, "", &listed, &mut unlisted)?;
+    if !unlisted.is_empty() {
+        return Err(unlisted_error(&unlisted));
+    }
+
+    verify_bundle(dir, None, &[])
+}
+
+// Classpath entries outside of `app_dir` are not part of the bundle, e.g. jar served from self-extracting starter.
+pub fn verify_listed(app_dir: &Path, paths: &[PathBuf]) -> Result<(), Box<dyn Error>> {
+    let listed = match listed_files(app_dir)? {
+        Some(listed) => listed,
+        None => return Ok(()),
+    };
+
+    let unlisted = paths
+        .iter()
+        .filter(|path| path.starts_with(app_dir))
+        .filter(|path| !bundle_name(app_dir, path).is_some_and(|name| listed.contains(&name)))
+        .map(|path| path.display().to_string())
+        .collect::<Vec<_>>();
+    if !unlisted.is_empty() {
+        return Err(unlisted_error(&unlisted));
+    }
+
+    Ok(())
+}
@@ -0,0 +1,96 @@
+use std::{
+    cmp::Ordering,
+    error::Error,
+    path::{Component, Path, PathBuf},
+};
+
+use crate::utils::version::compare_versions;
+
+pub const VERSIONS_DIR_NAME: &str = "versions";
+const CURRENT_POINTER_NAME: &str = "current";
+
+// Either the legacy layout with jar and runtime next to the starter, or
+//   <app dir>/versions/<version>/{clash-multiplatform.jar,jre,...}
+//   <app dir>/current   (file containing version name, or symlink to version directory)
+pub struct InstallLayout {
+    pub app_dir: PathBuf,
+    pub root: PathBuf,
+    pub version: Option<String>,
+}
+
+pub fn is_valid_version_name(name: &str) -> bool {
+    let mut components = Path::new(name).components();
+
+    matches!(components.next(), Some(Component::Normal(_))) && components.next().is_none()
+}
+
+pub fn current_version(app_dir: &Path) -> Option<String> {
+    let pointer = app_dir.join(CURRENT_POINTER_NAME);
+
+    let version = if pointer.is_symlink() {
+        std::fs::read_link(&pointer).ok()?.file_name()?.to_str()?.to_owned()
+    } else {
+        std::fs::read_to_string(&pointer).ok()?.trim().to_owned()
+    };
+
+    Some(version).filter(|v| is_valid_version_name(v))
+}
+
+// Replaces pointer with a rename, so it always points to a complete version.
+pub fn set_current_version(app_dir: &Path, version: &str) -> std::io::Result<()> {
+    let pointer = app_dir.join(CURRENT_POINTER_NAME);
+    let temp = app_dir.join(format!("{}.tmp", CURRENT_POINTER_NAME));
+
+    std::fs::write(&temp, version)?;
+
+    if pointer.is_dir() && !pointer.is_symlink() {
+        std::fs::remove_dir_all(&pointer)?;
+    }
+
+    std::fs::rename(&temp, &pointer)
+}
+
+fn newest_installed_version(versions_dir: &Path) -> Option<String> {
+    std::fs::read_dir(versions_dir)
+        .ok()?
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.path().is_dir())
+        .filter_map(|entry| entry.file_name().into_string().ok())
+        .filter(|name| !name.starts_with('.'))
+        .max_by(|a, b| match compare_versions(a, b) {
+            Ordering::Equal => a.cmp(b),
+            ordering => ordering,
+        })
+}
+
+pub fn resolve_layout(app_dir: &Path, use_version: Option<&str>) -> Result<InstallLayout, Box<dyn Error>> {
+    let versions_dir = app_dir.join(VERSIONS_DIR_NAME);
+
+    let version = match use_version {
+        Some(version) => {
+            if !is_valid_version_name(version) || !versions_dir.join(version).is_dir() {
+                return Err(format!("Version {} is not installed", version).into());
+            }
+
+            Some(version.to_owned())
+        }
+        None if versions_dir.is_dir() => Some(
+            current_version(app_dir)
+                .filter(|v| versions_dir.join(v).is_dir())
+                .or_else(|| newest_installed_version(&versions_dir))
+                .ok_or("No installed version found")?,
+        ),
+        None => None,
+    };
+
+    let root = match &version {
+        Some(version) => versions_dir.join(version),
+        None => app_dir.to_path_buf(),
+    };
+
+    Ok(InstallLayout {
+        app_dir: app_dir.to_path_buf(),
+        root,
+        version,
+    })
+}
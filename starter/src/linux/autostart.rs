@@ -0,0 +1,147 @@
+use std::{
+    error::Error,
+    io,
+    path::PathBuf,
+    process::{Command, Stdio},
+};
+
+use crate::{
+    linux::{
+        desktop::{escape_value, quote_exec_argument, write_atomically},
+        dirs::current_user_config_directory,
+    },
+    options::AutostartMethod,
+};
+
+const AUTOSTART_DIR_NAME: &str = "autostart";
+const SYSTEMD_USER_DIR_NAME: &str = "systemd/user";
+
+fn entry_path(method: AutostartMethod, id: &str) -> Result<PathBuf, Box<dyn Error>> {
+    let config_dir = current_user_config_directory()?;
+
+    Ok(match method {
+        AutostartMethod::Desktop => config_dir.join(AUTOSTART_DIR_NAME).join(format!("{}.desktop", id)),
+        AutostartMethod::Systemd => config_dir.join(SYSTEMD_USER_DIR_NAME).join(format!("{}.service", id)),
+    })
+}
+
+// systemd splits like a shell but expands `%` specifiers and `$` variables.
+fn quote_systemd_argument(arg: &str) -> String {
+    let arg = arg.replace('%', "%%").replace('$', "$$");
+
+    if arg.is_empty() || arg.contains(|c: char| c.is_whitespace() || "\"'\\;".contains(c)) {
+        format!("\"{}\"", arg.replace('\\', "\\\\").replace('"', "\\\""))
+    } else {
+        arg
+    }
+}
+
+// Values end at line break, a trailing backslash would continue them into the next line.
+fn sanitize_systemd_value(value: &str) -> String {
+    let value = value
+        .chars()
+        .map(|c| if c.is_control() { ' ' } else { c })
+        .collect::<String>()
+        .replace('%', "%%");
+
+    value.trim_end_matches(['\\', ' ']).to_owned()
+}
+
+fn systemctl(args: &[&str]) -> Result<bool, Box<dyn Error>> {
+    let systemctl = which::which("systemctl").map_err(|_| "systemctl not found")?;
+
+    let status = Command::new(systemctl)
+        .arg("--user")
+        .args(args)
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()?;
+
+    Ok(status.success())
+}
+
+fn desktop_entry(name: &str, exec: &[String]) -> String {
+    let exec = exec.iter().map(|arg| quote_exec_argument(arg)).collect::<Vec<_>>().join(" ");
+
+    format!(
+        "[Desktop Entry]\nType=Application\nName={}\nExec={}\nTerminal=false\nX-GNOME-Autostart-enabled=true\n",
+        escape_value(name),
+        escape_value(&exec)
+    )
+}
+
+fn systemd_unit(name: &str, exec: &[String]) -> String {
+    let exec = exec
+        .iter()
+        .map(|arg| quote_systemd_argument(arg))
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    format!(
+        "[Unit]\nDescription={}\nAfter=network-online.target\n\n\
+         [Service]\nType=simple\nExecStart={}\nRestart=on-failure\n\n\
+         [Install]\nWantedBy=default.target\n",
+        sanitize_systemd_value(name),
+        exec
+    )
+}
+
+// Rewrites the entry on every call, so enabling again picks up a moved starter.
+pub fn enable_autostart(method: AutostartMethod, id: &str, name: &str, exec: &[String]) -> Result<PathBuf, Box<dyn Error>> {
+    let path = entry_path(method, id)?;
+    let content = match method {
+        AutostartMethod::Desktop => desktop_entry(name, exec),
+        AutostartMethod::Systemd => systemd_unit(name, exec),
+    };
+
+    std::fs::create_dir_all(path.parent().unwrap())?;
+    write_atomically(&path, content.as_bytes())?;
+
+    if method == AutostartMethod::Systemd {
+        let unit = format!("{}.service", id);
+
+        if !systemctl(&["daemon-reload"]).unwrap_or(false) || !systemctl(&["enable", &unit]).unwrap_or(false) {
+            let _ = std::fs::remove_file(&path);
+
+            return Err(format!("Unable to enable {} with systemctl", unit).into());
+        }
+    }
+
+    Ok(path)
+}
+
+pub fn disable_autostart(method: AutostartMethod, id: &str) -> Result<PathBuf, Box<dyn Error>> {
+    let path = entry_path(method, id)?;
+
+    if method == AutostartMethod::Systemd && path.exists() {
+        systemctl(&["disable", &format!("{}.service", id)])?;
+    }
+
+    match std::fs::remove_file(&path) {
+        Err(err) if err.kind() != io::ErrorKind::NotFound => return Err(err.into()),
+        _ => {}
+    }
+
+    if method == AutostartMethod::Systemd {
+        let _ = systemctl(&["daemon-reload"]);
+    }
+
+    Ok(path)
+}
+
+pub fn is_autostart_enabled(method: AutostartMethod, id: &str) -> Result<(bool, PathBuf), Box<dyn Error>> {
+    let path = entry_path(method, id)?;
+
+    let enabled = match method {
+        AutostartMethod::Desktop => std::fs::read_to_string(&path).is_ok_and(|content| {
+            !content
+                .lines()
+                .any(|line| line == "Hidden=true" || line == "X-GNOME-Autostart-enabled=false")
+        }),
+        AutostartMethod::Systemd => {
+            path.exists() && systemctl(&["is-enabled", "--quiet", &format!("{}.service", id)]).unwrap_or(false)
+        }
+    };
+
+    Ok((enabled, path))
+}
@@ -0,0 +1,535 @@
+use std::{
+    error::Error,
+    io::{BufRead, BufReader, Read, Write},
+    os::{
+        linux::net::SocketAddrExt,
+        unix::net::{SocketAddr, UnixStream},
+    },
+    path::PathBuf,
+    time::Duration,
+};
+
+use crate::utils::strings::{decode_percent, encode_hex};
+
+const TIMEOUT: Duration = Duration::from_secs(5);
+const MAX_MESSAGE_LENGTH: usize = 1024 * 1024;
+
+const MESSAGE_METHOD_CALL: u8 = 1;
+const MESSAGE_METHOD_RETURN: u8 = 2;
+const MESSAGE_ERROR: u8 = 3;
+
+const FIELD_PATH: u8 = 1;
+const FIELD_INTERFACE: u8 = 2;
+const FIELD_MEMBER: u8 = 3;
+const FIELD_ERROR_NAME: u8 = 4;
+const FIELD_REPLY_SERIAL: u8 = 5;
+const FIELD_DESTINATION: u8 = 6;
+const FIELD_SIGNATURE: u8 = 8;
+
+// Only the argument types desktop notifications need.
+pub enum Value<'a> {
+    Byte(u8),
+    Int32(i32),
+    Uint32(u32),
+    String(&'a str),
+    StringArray(&'a [&'a str]),
+    // `a{sv}`, values are wrapped into variants.
+    VariantDict(&'a [(&'a str, Value<'a>)]),
+}
+
+impl Value<'_> {
+    fn signature(&self) -> &'static str {
+        match self {
+            Value::Byte(_) => "y",
+            Value::Int32(_) => "i",
+            Value::Uint32(_) => "u",
+            Value::String(_) => "s",
+            Value::StringArray(_) => "as",
+            Value::VariantDict(_) => "a{sv}",
+        }
+    }
+}
+
+#[derive(Default)]
+struct Writer {
+    buf: Vec<u8>,
+}
+
+impl Writer {
+    fn align(&mut self, alignment: usize) {
+        self.buf.resize(self.buf.len().next_multiple_of(alignment), 0);
+    }
+
+    fn u32(&mut self, value: u32) {
+        self.align(4);
+        self.buf.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn string(&mut self, value: &str) {
+        self.u32(value.len() as u32);
+        self.buf.extend_from_slice(value.as_bytes());
+        self.buf.push(0);
+    }
+
+    fn signature(&mut self, value: &str) {
+        self.buf.push(value.len() as u8);
+        self.buf.extend_from_slice(value.as_bytes());
+        self.buf.push(0);
+    }
+
+    // Array length excludes the padding before first element.
+    fn array(&mut self, element_alignment: usize, write_elements: impl FnOnce(&mut Self)) {
+        self.u32(0);
+        let length_offset = self.buf.len() - 4;
+
+        self.align(element_alignment);
+        let start = self.buf.len();
+        write_elements(self);
+
+        let length = (self.buf.len() - start) as u32;
+        self.buf[length_offset..length_offset + 4].copy_from_slice(&length.to_le_bytes());
+    }
+
+    fn value(&mut self, value: &Value) {
+        match value {
+            Value::Byte(value) => self.buf.push(*value),
+            Value::Int32(value) => self.u32(*value as u32),
+            Value::Uint32(value) => self.u32(*value),
+            Value::String(value) => self.string(value),
+            Value::StringArray(values) => self.array(4, |w| values.iter().for_each(|value| w.string(value))),
+            Value::VariantDict(entries) => self.array(8, |w| {
+                for (key, value) in entries.iter() {
+                    w.align(8);
+                    w.string(key);
+                    w.signature(value.signature());
+                    w.value(value);
+                }
+            }),
+        }
+    }
+}
+
+struct Reply {
+    kind: u8,
+    reply_serial: Option<u32>,
+    error_name: Option<String>,
+    body: Vec<u8>,
+}
+
+fn read_u32(bytes: &[u8], offset: usize, big_endian: bool) -> Option<u32> {
+    let bytes: [u8; 4] = bytes.get(offset..offset + 4)?.try_into().ok()?;
+
+    Some(if big_endian {
+        u32::from_be_bytes(bytes)
+    } else {
+        u32::from_le_bytes(bytes)
+    })
+}
+
+fn read_message<R: Read>(reader: &mut R) -> Result<Reply, Box<dyn Error>> {
+    let invalid = || "Invalid D-Bus message";
+
+    let mut fixed = [0u8; 16];
+    reader.read_exact(&mut fixed)?;
+
+    let big_endian = fixed[0] == b'B';
+    let body_length = read_u32(&fixed, 4, big_endian).ok_or_else(invalid)? as usize;
+    let fields_length = read_u32(&fixed, 12, big_endian).ok_or_else(invalid)? as usize;
+
+    let header_length = (16 + fields_length).next_multiple_of(8);
+    if header_length + body_length > MAX_MESSAGE_LENGTH {
+        return Err(invalid().into());
+    }
+
+    let mut message = fixed.to_vec();
+    message.resize(header_length + body_length, 0);
+    reader.read_exact(&mut message[16..])?;
+
+    let mut reply = Reply {
+        kind: fixed[1],
+        reply_serial: None,
+        error_name: None,
+        body: message.split_off(header_length),
+    };
+
+    let mut offset = 16;
+    while offset < 16 + fields_length {
+        offset = offset.next_multiple_of(8);
+
+        let code = *message.get(offset).ok_or_else(invalid)?;
+        let signature_length = *message.get(offset + 1).ok_or_else(invalid)? as usize;
+        let signature = message.get(offset + 2..offset + 2 + signature_length).ok_or_else(invalid)?;
+        offset += 3 + signature_length;
+
+        match signature {
+            b"u" => {
+                offset = offset.next_multiple_of(4);
+                let value = read_u32(&message, offset, big_endian).ok_or_else(invalid)?;
+                offset += 4;
+
+                if code == FIELD_REPLY_SERIAL {
+                    reply.reply_serial = Some(value);
+                }
+            }
+            b"s" | b"o" => {
+                offset = offset.next_multiple_of(4);
+                let length = read_u32(&message, offset, big_endian).ok_or_else(invalid)? as usize;
+                let value = message.get(offset + 4..offset + 4 + length).ok_or_else(invalid)?;
+                offset += 5 + length;
+
+                if code == FIELD_ERROR_NAME {
+                    reply.error_name = Some(String::from_utf8_lossy(value).into_owned());
+                }
+            }
+            b"g" => offset += 2 + *message.get(offset).ok_or_else(invalid)? as usize,
+            _ => return Err(invalid().into()),
+        }
+    }
+
+    // Only the leading u32 of bodies is ever read, normalize it here.
+    if big_endian && reply.body.len() >= 4 {
+        reply.body[..4].reverse();
+    }
+
+    Ok(reply)
+}
+
+fn session_bus_address() -> Result<SocketAddr, Box<dyn Error>> {
+    if let Ok(addresses) = std::env::var("DBUS_SESSION_BUS_ADDRESS") {
+        for address in addresses.split(';') {
+            let Some(parameters) = address.strip_prefix("unix:") else {
+                continue;
+            };
+
+            for parameter in parameters.split(',') {
+                match parameter.split_once('=') {
+                    Some(("path", path)) => {
+                        return Ok(SocketAddr::from_pathname(
+                            decode_percent(path, false).ok_or("Invalid bus path")?,
+                        )?)
+                    }
+                    Some(("abstract", name)) => {
+                        return Ok(SocketAddr::from_abstract_name(
+                            decode_percent(name, false).ok_or("Invalid bus name")?,
+                        )?)
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        return Err(format!("Unsupported session bus address {}", addresses).into());
+    }
+
+    let runtime_dir = std::env::var_os("XDG_RUNTIME_DIR").ok_or("Session bus not found")?;
+
+    Ok(SocketAddr::from_pathname(PathBuf::from(runtime_dir).join("bus"))?)
+}
+
+pub struct Connection {
+    stream: BufReader<UnixStream>,
+    serial: u32,
+}
+
+impl Connection {
+    pub fn open_session_bus() -> Result<Self, Box<dyn Error>> {
+        let stream = UnixStream::connect_addr(&session_bus_address()?)?;
+        stream.set_read_timeout(Some(TIMEOUT))?;
+        stream.set_write_timeout(Some(TIMEOUT))?;
+
+        let mut stream = BufReader::new(stream);
+        let uid = unsafe { libc::getuid() }.to_string();
+        stream
+            .get_mut()
+            .write_all(format!("\0AUTH EXTERNAL {}\r\n", encode_hex(uid.as_bytes())).as_bytes())?;
+
+        let mut line = String::new();
+        stream.read_line(&mut line)?;
+        if !line.starts_with("OK ") {
+            return Err(format!("D-Bus authentication rejected: {}", line.trim()).into());
+        }
+
+        stream.get_mut().write_all(b"BEGIN\r\n")?;
+
+        let mut connection = Connection { stream, serial: 0 };
+        connection.call(
+            "org.freedesktop.DBus",
+            "/org/freedesktop/DBus",
+            "org.freedesktop.DBus",
+            "Hello",
+            &[],
+        )?;
+
+        Ok(connection)
+    }
+
+    // Returns leading u32 of the reply body, if any.
+    pub fn call(
+        &mut self,
+        destination: &str,
+        path: &str,
+        interface: &str,
+        member: &str,
+        args: &[Value],
+    ) -> Result<Option<u32>, Box<dyn Error>> {
+        self.serial += 1;
+
+        let signature = args.iter().map(Value::signature).collect::<String>();
+        let mut body = Writer::default();
+        for arg in args {
+            body.value(arg);
+        }
+
+        let mut message = Writer::default();
+        message.buf.extend_from_slice(&[b'l', MESSAGE_METHOD_CALL, 0, 1]);
+        message.u32(body.buf.len() as u32);
+        message.u32(self.serial);
+        message.array(8, |w| {
+            let mut field = |code: u8, signature: &str, write: &dyn Fn(&mut Writer)| {
+                w.align(8);
+                w.buf.push(code);
+                w.signature(signature);
+                write(w);
+            };
+
+            field(FIELD_PATH, "o", &|w| w.string(path));
+            field(FIELD_INTERFACE, "s", &|w| w.string(interface));
+            field(FIELD_MEMBER, "s", &|w| w.string(member));
+            field(FIELD_DESTINATION, "s", &|w| w.string(destination));
+            if !signature.is_empty() {
+                field(FIELD_SIGNATURE, "g", &|w| w.signature(&signature));
+            }
+        });
+        message.align(8);
+        message.buf.extend_from_slice(&body.buf);
+
+        self.stream.get_mut().write_all(&message.buf)?;
+
+        // Signals and unrelated replies arrive in between.
+        loop {
+            let reply = read_message(&mut self.stream)?;
+            if reply.reply_serial != Some(self.serial) {
+                continue;
+            }
+
+            return match reply.kind {
+                MESSAGE_METHOD_RETURN => Ok(reply.body.get(..4).map(|b| u32::from_le_bytes(b.try_into().unwrap()))),
+                MESSAGE_ERROR => Err(reply.error_name.unwrap_or_else(|| "D-Bus call failed".to_owned()).into()),
+                _ => Err("Unexpected D-Bus reply".into()),
+            };
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::{BufRead, BufReader},
+        process::{Command, Stdio},
+    };
+
+    use super::*;
+
+    // Marshals messages in either byte order, field values are `u`, `s`, `o` or `g`.
+    struct TestMessage {
+        big_endian: bool,
+        fields: Vec<u8>,
+    }
+
+    impl TestMessage {
+        fn new(big_endian: bool) -> Self {
+            TestMessage {
+                big_endian,
+                fields: Vec::new(),
+            }
+        }
+
+        fn u32_bytes(&self, value: u32) -> [u8; 4] {
+            if self.big_endian {
+                value.to_be_bytes()
+            } else {
+                value.to_le_bytes()
+            }
+        }
+
+        fn field(&mut self, code: u8, signature: u8) {
+            self.fields.resize(self.fields.len().next_multiple_of(8), 0);
+            self.fields.extend_from_slice(&[code, 1, signature, 0]);
+        }
+
+        fn field_u32(mut self, code: u8, value: u32) -> Self {
+            self.field(code, b'u');
+            self.fields.extend_from_slice(&self.u32_bytes(value));
+
+            self
+        }
+
+        fn field_string(mut self, code: u8, signature: u8, value: &str) -> Self {
+            self.field(code, signature);
+            if signature == b'g' {
+                self.fields.push(value.len() as u8);
+            } else {
+                self.fields.extend_from_slice(&self.u32_bytes(value.len() as u32));
+            }
+            self.fields.extend_from_slice(value.as_bytes());
+            self.fields.push(0);
+
+            self
+        }
+
+        fn finish(self, kind: u8, body: Option<u32>) -> Vec<u8> {
+            let body = body.map(|value| self.u32_bytes(value).to_vec()).unwrap_or_default();
+
+            let mut message = vec![if self.big_endian { b'B' } else { b'l' }, kind, 0, 1];
+            message.extend_from_slice(&self.u32_bytes(body.len() as u32));
+            message.extend_from_slice(&self.u32_bytes(9));
+            message.extend_from_slice(&self.u32_bytes(self.fields.len() as u32));
+            message.extend_from_slice(&self.fields);
+            message.resize(message.len().next_multiple_of(8), 0);
+            message.extend_from_slice(&body);
+
+            message
+        }
+    }
+
+    #[test]
+    fn marshals_notify_arguments() {
+        let mut writer = Writer::default();
+        for value in [
+            Value::String("app"),
+            Value::Uint32(0),
+            Value::String(""),
+            Value::String("S"),
+            Value::String("B"),
+            Value::StringArray(&[]),
+            Value::VariantDict(&[("urgency", Value::Byte(1))]),
+            Value::Int32(-1),
+        ] {
+            writer.value(&value);
+        }
+
+        #[rustfmt::skip]
+        let expected: &[u8] = &[
+            3, 0, 0, 0, b'a', b'p', b'p', 0,
+            0, 0, 0, 0,
+            0, 0, 0, 0, 0, 0, 0, 0,
+            1, 0, 0, 0, b'S', 0, 0, 0,
+            1, 0, 0, 0, b'B', 0, 0, 0,
+            0, 0, 0, 0,
+            // Dict length excludes padding to the first 8-aligned entry.
+            16, 0, 0, 0, 0, 0, 0, 0,
+            7, 0, 0, 0, b'u', b'r', b'g', b'e', b'n', b'c', b'y', 0,
+            1, b'y', 0, 1,
+            0xff, 0xff, 0xff, 0xff,
+        ];
+        assert_eq!(writer.buf, expected);
+    }
+
+    #[test]
+    fn reads_method_return_in_both_byte_orders() {
+        for big_endian in [false, true] {
+            let message = TestMessage::new(big_endian)
+                .field_u32(FIELD_REPLY_SERIAL, 3)
+                .field_string(FIELD_DESTINATION, b's', ":1.7")
+                .field_string(FIELD_SIGNATURE, b'g', "u")
+                .finish(MESSAGE_METHOD_RETURN, Some(42));
+
+            let reply = read_message(&mut message.as_slice()).unwrap();
+
+            assert_eq!(reply.kind, MESSAGE_METHOD_RETURN);
+            assert_eq!(reply.reply_serial, Some(3));
+            assert_eq!(reply.body, 42u32.to_le_bytes());
+        }
+    }
+
+    #[test]
+    fn reads_error_and_signal() {
+        let message = TestMessage::new(false)
+            .field_string(FIELD_ERROR_NAME, b's', "org.freedesktop.DBus.Error.Failed")
+            .field_u32(FIELD_REPLY_SERIAL, 5)
+            .finish(MESSAGE_ERROR, None);
+        let reply = read_message(&mut message.as_slice()).unwrap();
+
+        assert_eq!(reply.kind, MESSAGE_ERROR);
+        assert_eq!(reply.reply_serial, Some(5));
+        assert_eq!(reply.error_name.as_deref(), Some("org.freedesktop.DBus.Error.Failed"));
+
+        // Signals carry no reply serial, so calls skip them.
+        let signal = TestMessage::new(true)
+            .field_string(FIELD_PATH, b'o', "/org/freedesktop/DBus")
+            .field_string(FIELD_INTERFACE, b's', "org.freedesktop.DBus")
+            .field_string(FIELD_MEMBER, b's', "NameAcquired")
+            .finish(4, None);
+        let stream = [signal, message].concat();
+        let mut stream = stream.as_slice();
+
+        let reply = read_message(&mut stream).unwrap();
+        assert_eq!(reply.kind, 4);
+        assert_eq!(reply.reply_serial, None);
+
+        let reply = read_message(&mut stream).unwrap();
+        assert_eq!(reply.reply_serial, Some(5));
+        assert!(stream.is_empty());
+    }
+
+    #[test]
+    fn rejects_oversized_message() {
+        let mut message = TestMessage::new(false).finish(MESSAGE_METHOD_RETURN, None);
+        message[4..8].copy_from_slice(&(MAX_MESSAGE_LENGTH as u32).to_le_bytes());
+
+        assert!(read_message(&mut message.as_slice()).is_err());
+    }
+
+    #[test]
+    fn talks_to_session_bus() {
+        let Ok(daemon) = which::which("dbus-daemon") else {
+            eprintln!("dbus-daemon not found, skipped");
+
+            return;
+        };
+
+        let mut daemon = Command::new(daemon)
+            .args(["--session", "--nofork", "--print-address"])
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .unwrap();
+
+        let mut address = String::new();
+        BufReader::new(daemon.stdout.as_mut().unwrap())
+            .read_line(&mut address)
+            .unwrap();
+        std::env::set_var("DBUS_SESSION_BUS_ADDRESS", address.trim());
+
+        let result = (|| -> Result<(), Box<dyn Error>> {
+            let mut connection = Connection::open_session_bus()?;
+            let call = |connection: &mut Connection, member: &str, args: &[Value]| {
+                connection.call(
+                    "org.freedesktop.DBus",
+                    "/org/freedesktop/DBus",
+                    "org.freedesktop.DBus",
+                    member,
+                    args,
+                )
+            };
+
+            // 1 is DBUS_REQUEST_NAME_REPLY_PRIMARY_OWNER.
+            let name = "com.github.kr328.clash.Test";
+            assert_eq!(
+                call(&mut connection, "RequestName", &[Value::String(name), Value::Uint32(0)])?,
+                Some(1)
+            );
+            assert_eq!(call(&mut connection, "NameHasOwner", &[Value::String(name)])?, Some(1));
+
+            let err = call(&mut connection, "NoSuchMethod", &[]).err().unwrap();
+            assert_eq!(err.to_string(), "org.freedesktop.DBus.Error.UnknownMethod");
+
+            Ok(())
+        })();
+
+        let _ = daemon.kill();
+        let _ = daemon.wait();
+
+        result.unwrap();
+    }
+}
@@ -0,0 +1,165 @@
+use std::{
+    error::Error,
+    io,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+};
+
+use crate::linux::dirs::current_user_data_directory;
+
+const APPLICATIONS_DIR_NAME: &str = "applications";
+const HICOLOR_DIR_NAME: &str = "icons/hicolor";
+
+pub struct DesktopEntry<'a> {
+    pub id: &'a str,
+    pub name: &'a str,
+    pub exec: &'a [String],
+    pub url_scheme: &'a str,
+    pub icons_dir: &'a Path,
+}
+
+// Escape rules of `Exec` key, applied before the general string escaping of values.
+pub fn quote_exec_argument(arg: &str) -> String {
+    let arg = arg.replace('%', "%%");
+
+    let reserved = |c: char| c.is_whitespace() || "\"'\\><~|&;$*?#()`".contains(c);
+    if arg.is_empty() || arg.contains(reserved) {
+        let escaped = arg
+            .chars()
+            .flat_map(|c| match c {
+                '"' | '`' | '$' | '\\' => vec!['\\', c],
+                c => vec![c],
+            })
+            .collect::<String>();
+
+        format!("\"{}\"", escaped)
+    } else {
+        arg
+    }
+}
+
+pub fn escape_value(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('\n', "\\n")
+        .replace('\t', "\\t")
+        .replace('\r', "\\r")
+}
+
+pub fn write_atomically(path: &Path, content: &[u8]) -> io::Result<()> {
+    let mut temp = path.to_path_buf().into_os_string();
+    temp.push(".tmp");
+
+    std::fs::write(&temp, content)?;
+    std::fs::rename(&temp, path)
+}
+
+// Accepts `<size>.png`, `<size>x<size>.png` and `*.svg` as scalable.
+fn icon_target(icon: &Path, id: &str) -> Option<PathBuf> {
+    let stem = icon.file_stem()?.to_str()?;
+
+    match icon.extension()?.to_str()? {
+        "svg" => Some(PathBuf::from(format!("scalable/apps/{}.svg", id))),
+        "png" => {
+            let size: u32 = stem.split_once('x').map_or(stem, |(width, _)| width).parse().ok()?;
+
+            Some(PathBuf::from(format!("{}x{}/apps/{}.png", size, size, id)))
+        }
+        _ => None,
+    }
+}
+
+fn run_quietly(program: &str, args: &[&str]) {
+    if let Ok(program) = which::which(program) {
+        let _ = Command::new(program)
+            .args(args)
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status();
+    }
+}
+
+fn refresh_caches(applications_dir: &Path, hicolor_dir: &Path) {
+    run_quietly("update-desktop-database", &[&applications_dir.to_string_lossy()]);
+    run_quietly("gtk-update-icon-cache", &["-q", "-t", &hicolor_dir.to_string_lossy()]);
+}
+
+pub fn install_desktop_entry(entry: &DesktopEntry) -> Result<PathBuf, Box<dyn Error>> {
+    let data_dir = current_user_data_directory()?;
+    let applications_dir = data_dir.join(APPLICATIONS_DIR_NAME);
+    let hicolor_dir = data_dir.join(HICOLOR_DIR_NAME);
+
+    let mut installed_icon = false;
+    if let Ok(icons) = std::fs::read_dir(entry.icons_dir) {
+        for icon in icons {
+            let icon = icon?.path();
+
+            if let Some(target) = icon_target(&icon, entry.id) {
+                let target = hicolor_dir.join(target);
+
+                std::fs::create_dir_all(target.parent().unwrap())?;
+                write_atomically(&target, &std::fs::read(&icon)?)?;
+
+                installed_icon = true;
+            }
+        }
+    }
+
+    let exec = entry
+        .exec
+        .iter()
+        .map(|arg| quote_exec_argument(arg))
+        .collect::<Vec<_>>()
+        .join(" ");
+    let mut content = format!(
+        "[Desktop Entry]\nType=Application\nName={}\nExec={} %u\nTerminal=false\nCategories=Network;\nMimeType=x-scheme-handler/{};\n",
+        escape_value(entry.name),
+        escape_value(&exec),
+        entry.url_scheme
+    );
+    if installed_icon {
+        content.push_str(&format!("Icon={}\n", entry.id));
+    }
+
+    let desktop_file = applications_dir.join(format!("{}.desktop", entry.id));
+    std::fs::create_dir_all(&applications_dir)?;
+    write_atomically(&desktop_file, content.as_bytes())?;
+
+    run_quietly(
+        "xdg-mime",
+        &[
+            "default",
+            &format!("{}.desktop", entry.id),
+            &format!("x-scheme-handler/{}", entry.url_scheme),
+        ],
+    );
+    refresh_caches(&applications_dir, &hicolor_dir);
+
+    Ok(desktop_file)
+}
+
+pub fn uninstall_desktop_entry(id: &str) -> Result<PathBuf, Box<dyn Error>> {
+    let data_dir = current_user_data_directory()?;
+    let applications_dir = data_dir.join(APPLICATIONS_DIR_NAME);
+    let hicolor_dir = data_dir.join(HICOLOR_DIR_NAME);
+
+    if let Ok(sizes) = std::fs::read_dir(&hicolor_dir) {
+        for size in sizes {
+            let apps_dir = size?.path().join("apps");
+
+            for extension in ["png", "svg"] {
+                let _ = std::fs::remove_file(apps_dir.join(format!("{}.{}", id, extension)));
+            }
+        }
+    }
+
+    let desktop_file = applications_dir.join(format!("{}.desktop", id));
+    match std::fs::remove_file(&desktop_file) {
+        Err(err) if err.kind() != io::ErrorKind::NotFound => return Err(err.into()),
+        _ => {}
+    }
+
+    refresh_caches(&applications_dir, &hicolor_dir);
+
+    Ok(desktop_file)
+}
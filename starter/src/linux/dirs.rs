@@ -1,9 +1,33 @@
-use std::{error::Error, path::PathBuf};
+use std::{
+    error::Error,
+    path::{Path, PathBuf},
+};
+
+// Relative paths in XDG variables are invalid per specification and must be ignored.
+fn xdg_directory(variable: &str, fallback: &str) -> Result<PathBuf, Box<dyn Error>> {
+    if let Some(dir) = std::env::var_os(variable).filter(|dir| Path::new(dir).is_absolute()) {
+        return Ok(PathBuf::from(dir));
+    }
 
-pub fn current_user_config_directory() -> Result<PathBuf, Box<dyn Error>> {
     if let Some(dir) = home::home_dir() {
-        Ok(dir.join(".config"))
+        Ok(dir.join(fallback))
     } else {
         Err("User home directory not found".into())
     }
 }
+
+pub fn current_user_config_directory() -> Result<PathBuf, Box<dyn Error>> {
+    xdg_directory("XDG_CONFIG_HOME", ".config")
+}
+
+pub fn current_user_data_directory() -> Result<PathBuf, Box<dyn Error>> {
+    xdg_directory("XDG_DATA_HOME", ".local/share")
+}
+
+pub fn current_user_state_directory() -> Result<PathBuf, Box<dyn Error>> {
+    xdg_directory("XDG_STATE_HOME", ".local/state")
+}
+
+pub fn current_user_cache_directory() -> Result<PathBuf, Box<dyn Error>> {
+    xdg_directory("XDG_CACHE_HOME", ".cache")
+}
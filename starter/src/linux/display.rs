@@ -0,0 +1,43 @@
+use crate::headless::{DisplaySession, NO_DISPLAY_SERVER};
+
+const MAX_UI_SCALE: f32 = 8.0;
+
+fn non_empty_var(name: &str) -> Option<String> {
+    std::env::var(name).ok().filter(|value| !value.trim().is_empty())
+}
+
+// GDK_SCALE is the integer factor GTK desktops export, Java2D accepts the same value as `uiScale`.
+fn ui_scale() -> Option<String> {
+    let scale = non_empty_var("GDK_SCALE")?;
+    let value: f32 = scale.trim().parse().ok()?;
+
+    (value > 0.0 && value <= MAX_UI_SCALE).then(|| scale.trim().to_owned())
+}
+
+// AWT only speaks X11, so Wayland sessions are usable through XWayland only.
+pub fn detect_display_session() -> DisplaySession {
+    let x11 = non_empty_var("DISPLAY").is_some();
+    let wayland = non_empty_var("WAYLAND_DISPLAY").is_some()
+        || non_empty_var("XDG_SESSION_TYPE").is_some_and(|session| session == "wayland");
+
+    let server = match (wayland, x11) {
+        (true, _) => "wayland",
+        (false, true) => "x11",
+        (false, false) => NO_DISPLAY_SERVER,
+    };
+
+    let mut properties = Vec::new();
+    if x11 {
+        properties.push(("awt.toolkit.name", "XToolkit".to_owned()));
+
+        if let Some(scale) = ui_scale() {
+            properties.push(("sun.java2d.uiScale", scale));
+        }
+    }
+
+    DisplaySession {
+        server,
+        usable: x11,
+        properties,
+    }
+}
@@ -0,0 +1,98 @@
+use std::{
+    fs::{DirBuilder, File},
+    io,
+    os::{
+        fd::{AsRawFd, IntoRawFd},
+        unix::{
+            fs::{DirBuilderExt, MetadataExt},
+            net::{UnixListener, UnixStream},
+        },
+    },
+    path::{Path, PathBuf},
+    thread,
+    time::Duration,
+};
+
+use libc::{flock, getuid, EWOULDBLOCK, LOCK_EX, LOCK_NB};
+
+const CONNECT_ATTEMPTS: u32 = 20;
+const CONNECT_INTERVAL: Duration = Duration::from_millis(100);
+
+pub enum InstanceChannel {
+    Primary(UnixListener),
+    Secondary(UnixStream),
+}
+
+// Shared temporary directory is writable by everyone, so sockets live in a directory only the user can enter.
+pub fn private_socket_dir() -> io::Result<PathBuf> {
+    let uid = unsafe { getuid() };
+    let dir = std::env::temp_dir().join(format!("clash-multiplatform-{}", uid));
+
+    match DirBuilder::new().mode(0o700).create(&dir) {
+        Err(err) if err.kind() != io::ErrorKind::AlreadyExists => return Err(err),
+        _ => {}
+    }
+
+    let metadata = std::fs::symlink_metadata(&dir)?;
+    if !metadata.is_dir() || metadata.uid() != uid || metadata.mode() & 0o077 != 0 {
+        return Err(io::Error::new(
+            io::ErrorKind::PermissionDenied,
+            format!("{} is not a private directory", dir.display()),
+        ));
+    }
+
+    Ok(dir)
+}
+
+pub fn is_instance_locked(lock_path: &Path) -> io::Result<bool> {
+    let lock = match File::options().write(true).open(lock_path) {
+        Ok(lock) => lock,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(false),
+        Err(err) => return Err(err),
+    };
+
+    if unsafe { flock(lock.as_raw_fd(), LOCK_EX | LOCK_NB) } == 0 {
+        return Ok(false);
+    }
+
+    let err = io::Error::last_os_error();
+    if err.raw_os_error() == Some(EWOULDBLOCK) {
+        Ok(true)
+    } else {
+        Err(err)
+    }
+}
+
+// The lock decides which instance owns the socket, so a stale socket is never mistaken for a live one.
+pub fn open_instance_channel(lock_path: &Path, socket_path: &Path) -> io::Result<InstanceChannel> {
+    let lock = File::options().write(true).create(true).truncate(false).open(lock_path)?;
+
+    if unsafe { flock(lock.as_raw_fd(), LOCK_EX | LOCK_NB) } == 0 {
+        let _ = std::fs::remove_file(socket_path);
+        let listener = UnixListener::bind(socket_path)?;
+
+        // Held until process exits.
+        let _ = lock.into_raw_fd();
+
+        return Ok(InstanceChannel::Primary(listener));
+    }
+
+    let err = io::Error::last_os_error();
+    if err.raw_os_error() != Some(EWOULDBLOCK) {
+        return Err(err);
+    }
+
+    // Primary may still be binding its socket.
+    let mut attempts = 0;
+    loop {
+        match UnixStream::connect(socket_path) {
+            Ok(stream) => return Ok(InstanceChannel::Secondary(stream)),
+            Err(err) if attempts >= CONNECT_ATTEMPTS => return Err(err),
+            Err(_) => {
+                attempts += 1;
+
+                thread::sleep(CONNECT_INTERVAL);
+            }
+        }
+    }
+}
@@ -1,7 +1,8 @@
 use std::{
     error::Error,
-    ffi::{c_void, CString},
+    ffi::{c_void, CStr, CString},
     mem,
+    os::unix::ffi::OsStrExt,
     path::{Path, PathBuf},
     ptr::null_mut,
 };
@@ -22,8 +23,8 @@ impl Drop for JavaRuntime {
     fn drop(&mut self) {
         unsafe {
             let destroy_jvm = dlsym(self.jvm_handle, cstr!("DestroyJavaVM").as_ptr());
-            if destroy_jvm != null_mut() {
-                let destroy_jvm = mem::transmute::<_, unsafe extern "C" fn(*mut JavaVM) -> jint>(destroy_jvm);
+            if !destroy_jvm.is_null() {
+                let destroy_jvm = mem::transmute::<*mut c_void, unsafe extern "C" fn(*mut JavaVM) -> jint>(destroy_jvm);
 
                 destroy_jvm(self.vm);
             }
@@ -59,7 +60,7 @@ fn find_jvm_so(app_dir: &Path) -> Result<PathBuf, Box<dyn Error>> {
     }
 
     if let Ok(jre_path) = std::env::var("JAVA_HOME") {
-        if let Some(jvm_so) = find_jvm_so_by_jre_path(&Path::new(&jre_path)) {
+        if let Some(jvm_so) = find_jvm_so_by_jre_path(Path::new(&jre_path)) {
             return Ok(jvm_so);
         }
     }
@@ -67,31 +68,35 @@ fn find_jvm_so(app_dir: &Path) -> Result<PathBuf, Box<dyn Error>> {
     Err("JavaRuntime not found".into())
 }
 
-pub fn load_jvm(app_dir: &Path, args: &[&str]) -> Result<JavaRuntime, Box<dyn Error>> {
+pub fn load_jvm(app_dir: &Path, args: &[CString]) -> Result<JavaRuntime, Box<dyn Error>> {
     let jvm_so = find_jvm_so(app_dir)?;
 
-    std::env::set_var(
-        "LD_LIBRARY_PATH",
-        jvm_so.parent().unwrap().to_str().unwrap().to_owned() + ";" + &std::env::var("LD_LIBRARY_PATH").unwrap_or("".to_owned()),
-    );
+    // Paths are passed as raw bytes, install location may be outside of locale encoding.
+    let mut library_path = jvm_so.parent().ok_or("Invalid JavaRuntime path")?.as_os_str().to_owned();
+    if let Some(current) = std::env::var_os("LD_LIBRARY_PATH").filter(|p| !p.is_empty()) {
+        library_path.push(":");
+        library_path.push(current);
+    }
+    std::env::set_var("LD_LIBRARY_PATH", library_path);
 
-    let jvm_so = CString::new(jvm_so.to_string_lossy().to_string())?;
+    let jvm_so = CString::new(jvm_so.as_os_str().as_bytes()).map_err(|_| "JavaRuntime path contains NUL character")?;
 
     unsafe {
         let jvm_handle = dlopen(jvm_so.as_ptr(), RTLD_NOW);
-        if jvm_handle == null_mut() {
-            return Err(CString::from_raw(dlerror()).to_str()?.to_string().into());
+        if jvm_handle.is_null() {
+            return Err(CStr::from_ptr(dlerror()).to_string_lossy().into_owned().into());
         }
 
         let create_vm = dlsym(jvm_handle, cstr!("JNI_CreateJavaVM").as_ptr());
-        if create_vm == null_mut() {
-            return Err(CString::from_raw(dlerror()).to_str()?.to_string().into());
+        if create_vm.is_null() {
+            return Err(CStr::from_ptr(dlerror()).to_string_lossy().into_owned().into());
         }
 
-        let create_vm =
-            mem::transmute::<_, unsafe extern "C" fn(*mut *mut JavaVM, *mut *mut c_void, *mut c_void) -> jint>(create_vm);
+        let create_vm = mem::transmute::<
+            *mut c_void,
+            unsafe extern "C" fn(*mut *mut JavaVM, *mut *mut c_void, *mut c_void) -> jint,
+        >(create_vm);
 
-        let args = args.iter().map(|s| CString::new(*s).unwrap()).collect::<Vec<_>>();
         let options = args
             .iter()
             .map(|p| JavaVMOption {
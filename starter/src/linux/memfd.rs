@@ -0,0 +1,31 @@
+use std::{
+    error::Error,
+    ffi::CStr,
+    fs::File,
+    io::{self, Write},
+    os::fd::{FromRawFd, IntoRawFd},
+    path::PathBuf,
+};
+
+use libc::{
+    fcntl, memfd_create, F_ADD_SEALS, F_SEAL_GROW, F_SEAL_SEAL, F_SEAL_SHRINK, F_SEAL_WRITE, MFD_ALLOW_SEALING, MFD_CLOEXEC,
+};
+
+// Returns a path that stays valid for the lifetime of current process.
+pub fn create_sealed_file(name: &CStr, content: &[u8]) -> Result<PathBuf, Box<dyn Error>> {
+    let fd = unsafe { memfd_create(name.as_ptr(), MFD_CLOEXEC | MFD_ALLOW_SEALING) };
+    if fd < 0 {
+        return Err(io::Error::last_os_error().into());
+    }
+
+    let mut file = unsafe { File::from_raw_fd(fd) };
+    file.write_all(content)?;
+
+    unsafe {
+        if fcntl(fd, F_ADD_SEALS, F_SEAL_SHRINK | F_SEAL_GROW | F_SEAL_WRITE | F_SEAL_SEAL) < 0 {
+            return Err(io::Error::last_os_error().into());
+        }
+    }
+
+    Ok(PathBuf::from(format!("/proc/self/fd/{}", file.into_raw_fd())))
+}
@@ -1,3 +1,12 @@
+pub mod autostart;
+pub mod dbus;
+pub mod desktop;
 pub mod dirs;
+pub mod display;
+pub mod instance;
 pub mod jvm;
+pub mod memfd;
+pub mod notification;
 pub mod redirect;
+pub mod strings;
+pub mod ui;
@@ -0,0 +1,68 @@
+use std::{
+    error::Error,
+    process::{Command, Stdio},
+};
+
+use crate::{
+    linux::dbus::{Connection, Value},
+    notification::Notification,
+};
+
+fn notify_with_dbus(notification: &Notification) -> Result<(), Box<dyn Error>> {
+    let mut connection = Connection::open_session_bus()?;
+
+    let mut hints = vec![("urgency", Value::Byte(notification.urgency as u8))];
+    if !notification.app_id.is_empty() {
+        hints.push(("desktop-entry", Value::String(notification.app_id)));
+    }
+
+    connection.call(
+        "org.freedesktop.Notifications",
+        "/org/freedesktop/Notifications",
+        "org.freedesktop.Notifications",
+        "Notify",
+        &[
+            Value::String(notification.app_name),
+            Value::Uint32(0),
+            Value::String(notification.app_id),
+            Value::String(notification.summary),
+            Value::String(notification.body),
+            Value::StringArray(&[]),
+            Value::VariantDict(&hints),
+            Value::Int32(-1),
+        ],
+    )?;
+
+    Ok(())
+}
+
+fn notify_with_command(notification: &Notification) -> Result<(), Box<dyn Error>> {
+    let urgency = match notification.urgency as u8 {
+        0 => "--urgency=low",
+        1 => "--urgency=normal",
+        _ => "--urgency=critical",
+    };
+
+    let mut command = Command::new(which::which("notify-send")?);
+    command.args([urgency, "--app-name", notification.app_name]);
+    if !notification.app_id.is_empty() {
+        command.args(["--icon", notification.app_id]);
+    }
+
+    let status = command
+        .args([notification.summary, notification.body])
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()?;
+
+    if !status.success() {
+        return Err(format!("notify-send exited with {}", status).into());
+    }
+
+    Ok(())
+}
+
+pub fn show_notification(notification: &Notification) -> Result<(), Box<dyn Error>> {
+    notify_with_dbus(notification).or_else(|err| notify_with_command(notification).map_err(|_| err))
+}
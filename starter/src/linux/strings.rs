@@ -0,0 +1,104 @@
+use std::{
+    ffi::{CStr, CString, OsStr},
+    os::unix::ffi::OsStrExt,
+    ptr::{null, null_mut},
+    sync::OnceLock,
+};
+
+use cstr::cstr;
+use libc::{c_char, nl_langinfo, setlocale, size_t, wchar_t, CODESET, LC_CTYPE};
+
+extern "C" {
+    fn mbstowcs(dest: *mut wchar_t, src: *const c_char, n: size_t) -> size_t;
+    fn wcstombs(dest: *mut c_char, src: *const wchar_t, n: size_t) -> size_t;
+}
+
+enum Codeset {
+    Ascii,
+    Utf8,
+    Other,
+}
+
+// Runs `f` with LC_CTYPE of the environment, which is what JVM picks up as well.
+fn with_native_locale<T>(f: impl FnOnce() -> T) -> T {
+    unsafe {
+        let previous = setlocale(LC_CTYPE, null());
+        let previous = if previous.is_null() {
+            None
+        } else {
+            Some(CStr::from_ptr(previous).to_owned())
+        };
+
+        setlocale(LC_CTYPE, cstr!("").as_ptr());
+
+        let result = f();
+
+        if let Some(previous) = previous {
+            setlocale(LC_CTYPE, previous.as_ptr());
+        }
+
+        result
+    }
+}
+
+fn current_codeset() -> &'static Codeset {
+    static CODESET_CACHE: OnceLock<Codeset> = OnceLock::new();
+
+    CODESET_CACHE.get_or_init(|| {
+        let codeset = with_native_locale(|| {
+            unsafe { CStr::from_ptr(nl_langinfo(CODESET)) }
+                .to_string_lossy()
+                .to_uppercase()
+        });
+
+        match codeset.as_str() {
+            "UTF-8" | "UTF8" => Codeset::Utf8,
+            "ANSI_X3.4-1968" | "ASCII" | "US-ASCII" => Codeset::Ascii,
+            _ => Codeset::Other,
+        }
+    })
+}
+
+// JVM decodes bytes into strings and encodes them back when opening files, both must be lossless.
+fn round_trips_in_native_locale(bytes: &[u8]) -> bool {
+    let Ok(input) = CString::new(bytes) else {
+        return false;
+    };
+
+    with_native_locale(|| unsafe {
+        let length = mbstowcs(null_mut(), input.as_ptr(), 0);
+        if length == usize::MAX {
+            return false;
+        }
+
+        let mut wide: Vec<wchar_t> = vec![0; length + 1];
+        mbstowcs(wide.as_mut_ptr(), input.as_ptr(), wide.len());
+
+        let length = wcstombs(null_mut(), wide.as_ptr(), 0);
+        if length == usize::MAX {
+            return false;
+        }
+
+        let mut output = vec![0u8; length + 1];
+        wcstombs(output.as_mut_ptr().cast(), wide.as_ptr(), output.len());
+
+        &output[..length] == bytes
+    })
+}
+
+// JVM decodes option strings with the codeset of current locale.
+pub fn to_platform_encoding(s: &OsStr) -> Option<Vec<u8>> {
+    let bytes = s.as_bytes();
+
+    let representable = match current_codeset() {
+        Codeset::Ascii => bytes.is_ascii(),
+        Codeset::Utf8 => std::str::from_utf8(bytes).is_ok(),
+        Codeset::Other => round_trips_in_native_locale(bytes),
+    };
+
+    if representable {
+        Some(bytes.to_vec())
+    } else {
+        None
+    }
+}
@@ -0,0 +1,48 @@
+use std::{
+    fs::File,
+    io::{IsTerminal, Write},
+    process::{Command, Stdio},
+};
+
+fn has_display() -> bool {
+    ["DISPLAY", "WAYLAND_DISPLAY"]
+        .iter()
+        .any(|name| std::env::var_os(name).is_some_and(|value| !value.is_empty()))
+}
+
+// Dismissing a dialog exits with failure too, so only a tool that could not be started gives way to the next one.
+fn run_dialog(program: &str, args: &[&str]) -> bool {
+    let program = match which::which(program) {
+        Ok(program) => program,
+        Err(_) => return false,
+    };
+
+    Command::new(program)
+        .args(args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .is_ok()
+}
+
+// Tools are tried in order of how well they render long messages, blocking until user dismisses the dialog.
+fn show_dialog(title: &str, msg: &str) -> bool {
+    run_dialog("zenity", &["--error", "--no-markup", "--title", title, "--text", msg])
+        || run_dialog("kdialog", &["--title", title, "--error", msg])
+        || run_dialog("notify-send", &["--urgency=critical", "--app-name", title, title, msg])
+        || run_dialog("xmessage", &["-center", "-title", title, &format!("{}\n\n{}", title, msg)])
+}
+
+pub fn show_error_message(title: &str, msg: &str) {
+    if has_display() && show_dialog(title, msg) {
+        return;
+    }
+
+    // Stderr already went to the terminal unless it was redirected into log file.
+    if !std::io::stderr().is_terminal() {
+        if let Ok(mut tty) = File::options().write(true).open("/dev/tty") {
+            let _ = tty.write_fmt(format_args!("{}: {}\n", title, msg));
+        }
+    }
+}
@@ -3,16 +3,19 @@ use std::{
     fs::File,
     io,
     io::{BufRead, Write},
-    path::Path,
+    path::{Path, PathBuf},
+    sync::OnceLock,
 };
 
 const MAX_LOGFILE_LENGTH: usize = 20 * 1024 * 1024;
 
+static ERROR_LOG_FILE: OnceLock<PathBuf> = OnceLock::new();
+
 struct Discard {}
 
 impl Write for Discard {
     fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
-        return Ok(buf.len());
+        Ok(buf.len())
     }
 
     fn flush(&mut self) -> io::Result<()> {
@@ -92,7 +95,12 @@ pub fn redirect_stdout_to_logfile(base_dir: &Path) -> Result<(), Box<dyn Error>>
         }
     });
 
-    return Ok(());
+    Ok(())
+}
+
+// Pointed to by error reports, so users know where the details went.
+pub fn error_log_file() -> Option<&'static Path> {
+    ERROR_LOG_FILE.get().map(PathBuf::as_path)
 }
 
 pub fn redirect_stderr_to_logfile(base_dir: &Path) -> Result<(), Box<dyn Error>> {
@@ -104,7 +112,8 @@ pub fn redirect_stderr_to_logfile(base_dir: &Path) -> Result<(), Box<dyn Error>>
         let _ = std::fs::rename(&log_file, &old_log_file);
     }
 
-    let file = File::options().write(true).truncate(true).create(true).open(log_file)?;
+    let file = File::options().write(true).truncate(true).create(true).open(&log_file)?;
+    let _ = ERROR_LOG_FILE.set(log_file);
 
     #[cfg(target_os = "linux")]
     {
@@ -1,18 +1,43 @@
 #![windows_subsystem = "windows"]
 
-use std::{error::Error, io::Write, path::Path, process::exit, ptr::null_mut};
+use std::{
+    error::Error,
+    io::Write,
+    path::{Path, PathBuf},
+    process::exit,
+    sync::atomic::{AtomicBool, Ordering},
+};
 
 use clap::Parser;
-use cstr::cstr;
-use jni_sys::JNI_TRUE;
 
 use crate::{
-    dirs::current_app_dir,
-    logging::{redirect_stderr_to_logfile, redirect_stdout_to_logfile},
-    metadata::resolve_app_metadata,
-    options::Options,
+    bridge::register_natives,
+    bundle::{create_bundle, STARTER_NAME},
+    classpath::{configure_launch_mode, resolve_classpath},
+    deeplink::DeepLink,
+    desktop::{install_desktop_entry, manage_autostart, uninstall_desktop_entry},
+    dirs::{current_app_dir, desktop_entry_id, legacy_base_dir, resolve_app_dirs, resolve_portable_dir},
+    entry::EntryPoint,
+    headless::{detect_display_session, resolve_headless},
+    instance::{forward_or_listen, InstanceMessage},
+    integrity::{verify_bundle, verify_listed, BUNDLE_HASHES_NAME},
+    layout::{resolve_layout, InstallLayout},
+    logging::{error_log_file, redirect_stderr_to_logfile, redirect_stdout_to_logfile},
+    metadata::{resolve_app_metadata, Metadata, DEFAULT_APP_NAME},
+    migration::migrate_legacy_data,
+    notification::{show_notification, Notification, Urgency},
+    options::{Command, Options},
+    payload::load_embedded_payload,
+    profile::{ensure_not_running, manage_profiles, running_profile},
+    signature::verify_signatures,
     startup::StartupParameters,
-    utils::{errors::ErrorExt, java::jcall, strings::PathExt},
+    update::{
+        apply_staged_update, has_staged_update,
+        pending::{check_pending_update, confirm_update, record_launch_failure},
+        restart_starter, stage_update, SwapResult,
+    },
+    utils::errors::ErrorExt,
+    vmoptions::VMOptions,
 };
 
 #[cfg(windows)]
@@ -21,85 +46,405 @@ mod win32;
 #[cfg(target_os = "linux")]
 mod linux;
 
+mod archive;
+mod bridge;
+mod bundle;
+mod classpath;
+mod deeplink;
+mod desktop;
 mod dirs;
+mod entry;
+mod headless;
+mod instance;
+mod integrity;
+mod layout;
 mod logging;
 mod metadata;
+mod migration;
+mod notification;
 mod options;
+mod payload;
+mod profile;
+mod signature;
 mod startup;
+mod update;
 mod utils;
+mod vmoptions;
 
 const APP_JAR_NAME: &str = "clash-multiplatform.jar";
+const APP_LIB_DIR_NAME: &str = "lib";
+const INTEGRITY_CACHE_NAME: &str = "integrity.cache";
 const MAX_HEAP_USAGE_MB: usize = 512;
 
-fn run_app(options: &Options) -> Result<(), Box<dyn Error>> {
-    let app_dir = current_app_dir().map_err(|e| e.with_message("App dir not found"))?;
-    let classes_jar = app_dir.join(APP_JAR_NAME);
-    let metadata = resolve_app_metadata(&classes_jar).map_err(|e| e.with_message("Resolve app metadata"))?;
-    let parameters = StartupParameters::new(options, &metadata).map_err(|e| e.with_message("Resolve startup parameters"))?;
+// Only launches of the app report errors in a dialog, command line usage gets them on stderr.
+static ERROR_DIALOG: AtomicBool = AtomicBool::new(false);
+
+fn run_app(
+    options: &Options,
+    layout: &InstallLayout,
+    classes_jar: &Path,
+    metadata: &Metadata,
+    portable_dir: Option<&Path>,
+) -> Result<(), Box<dyn Error>> {
+    metadata.check_starter_version()?;
+
+    let dirs = resolve_app_dirs(options, portable_dir, metadata).map_err(|e| e.with_message("Resolve app directories"))?;
+    let deep_link = options
+        .url
+        .as_deref()
+        .map(DeepLink::parse)
+        .transpose()
+        .map_err(|e| e.with_message("Parse deep link"))?;
+
+    // Second launches hand their link to the running instance before touching its files.
+    let message = deep_link.clone().map_or(InstanceMessage::Activate, InstanceMessage::Open);
+    let mut startup_logs = Vec::new();
+
+    std::fs::create_dir_all(&dirs.state)?;
+    match forward_or_listen(&dirs.state, &message) {
+        Ok(true) => {
+            _ = std::io::stderr().write_fmt(format_args!("[Starter] | Forwarded to running instance\n"));
+
+            return Ok(());
+        }
+        Ok(false) => {}
+        Err(err) => startup_logs.push(format!("[Starter] err={} | Single instance unavailable", err)),
+    }
+
+    if let Some(legacy_dir) = legacy_base_dir(options, portable_dir, metadata)? {
+        startup_logs.extend(migrate_legacy_data(&legacy_dir, &dirs, false));
+    }
+
+    let mut parameters = StartupParameters::new(options, &dirs, portable_dir.is_some(), deep_link)
+        .map_err(|e| e.with_message("Resolve startup parameters"))?;
+
+    // Headless apps must not create shortcuts or touch AWT, missing `java.desktop` or display forces it.
+    let display = detect_display_session();
+    let headless = resolve_headless(options, &layout.root, &display).map_err(|e| e.with_message("Detect display"))?;
+    parameters.display_server = display.server.to_owned();
+    if let Some(reason) = &headless {
+        ERROR_DIALOG.store(false, Ordering::Relaxed);
+
+        parameters.headless = true;
+        parameters.no_shortcut = true;
+        startup_logs.push(format!(
+            "[Starter] reason={} display={} | Running headless",
+            reason, display.server
+        ));
+    }
+
     let base_directory = Path::new(&parameters.base_directory);
+    let entry = EntryPoint::new(metadata).map_err(|e| e.with_message("Resolve entry point"))?;
+    let classpath = resolve_classpath(classes_jar, &layout.root, metadata).map_err(|e| e.with_message("Resolve classpath"))?;
+
+    let state_directory = Path::new(&parameters.state_directory);
+    let cache_directory = Path::new(&parameters.cache_directory);
 
     std::fs::create_dir_all(base_directory)?;
+    std::fs::create_dir_all(state_directory)?;
+    std::fs::create_dir_all(cache_directory)?;
+
+    let _ = redirect_stdout_to_logfile(state_directory);
+    let _ = redirect_stderr_to_logfile(state_directory);
+
+    _ = std::io::stderr().write_fmt(format_args!("[Starter] {} | Starting\n", metadata));
+
+    for line in startup_logs {
+        _ = std::io::stderr().write_fmt(format_args!("{}\n", line));
+    }
 
-    let _ = redirect_stdout_to_logfile(base_directory);
-    let _ = redirect_stderr_to_logfile(base_directory);
+    // Jar served from self-extracting starter is verified when loaded, it has no signature file.
+    // With a pinned key the hash list and its signature are required, so files can not escape verification.
+    let hashes_file = layout.root.join(BUNDLE_HASHES_NAME);
+    let signed_files = if classes_jar.starts_with(&layout.root) {
+        vec![classes_jar, hashes_file.as_path()]
+    } else {
+        vec![hashes_file.as_path()]
+    };
+    verify_signatures(&signed_files).map_err(|e| e.with_message("Verify signature"))?;
+    let starter_name = format!("{}{}", STARTER_NAME, std::env::consts::EXE_SUFFIX);
+    let optional = match layout.version {
+        Some(_) => vec![starter_name.as_str()],
+        None => Vec::new(),
+    };
+    verify_bundle(&layout.root, Some(&cache_directory.join(INTEGRITY_CACHE_NAME)), &optional)
+        .and_then(|_| verify_listed(&layout.root, &classpath))
+        .map_err(|e| e.with_message("Verify bundle"))?;
 
-    let classpath_opt = format!("-Djava.class.path={}", classes_jar.to_string_without_extend_length_mark());
-    let max_heap_opt = format!("-Xmx{}m", MAX_HEAP_USAGE_MB);
+    let mut vm_options = VMOptions::default();
+    configure_launch_mode(&mut vm_options, &classpath, metadata);
+    if headless.is_some() {
+        vm_options.property("java.awt.headless", "true");
+    } else {
+        for (key, value) in &display.properties {
+            vm_options.property(key, value);
+        }
+    }
 
-    let init_opts: [&str; 3] = [&classpath_opt, &max_heap_opt, "-XX:+UseSerialGC"];
+    let init_opts = vm_options
+        .flag(&format!("-Xmx{}m", MAX_HEAP_USAGE_MB))
+        .flag("-XX:+UseSerialGC")
+        .encode()
+        .map_err(|e| e.with_message("Encode JVM options"))?;
 
     #[cfg(windows)]
-    let runtime = win32::jvm::load_jvm(&app_dir, &init_opts).map_err(|e| e.with_message("Load JavaRuntime"))?;
+    let runtime = win32::jvm::load_jvm(&layout.root, &init_opts).map_err(|e| e.with_message("Load JavaRuntime"))?;
 
     #[cfg(target_os = "linux")]
-    let runtime = linux::jvm::load_jvm(&app_dir, &init_opts).map_err(|e| e.with_message("Load JavaRuntime"))?;
+    let runtime = linux::jvm::load_jvm(&layout.root, &init_opts).map_err(|e| e.with_message("Load JavaRuntime"))?;
+
+    register_natives(runtime.env, &layout.app_dir, metadata, options.profile_name());
+
+    entry.invoke(runtime.env, &parameters)?;
+
+    Ok(())
+}
 
-    let c_main = jcall!(runtime.env, FindClass, cstr!("com/github/kr328/clash/MainKt").as_ptr());
-    if c_main == null_mut() {
-        jcall!(runtime.env, ExceptionDescribe);
+// Without a window these events would go unnoticed, app metadata is read from the swapped bundle.
+fn notify_update_event(app_dir: &Path, profile: &str, summary: &str, urgency: Urgency) {
+    let metadata = resolve_layout(app_dir, None)
+        .ok()
+        .and_then(|layout| resolve_app_metadata(&layout.root.join(APP_JAR_NAME)).ok());
+    let (app_name, app_id) = match &metadata {
+        Some(metadata) => (metadata.name.clone(), desktop_entry_id(&metadata.edition, profile)),
+        None => (DEFAULT_APP_NAME.to_owned(), String::new()),
+    };
 
-        return Err("Invalid application package".into());
+    let notification = Notification {
+        app_name: &app_name,
+        app_id: &app_id,
+        summary,
+        body: &metadata.map(|m| m.to_string()).unwrap_or_default(),
+        urgency,
+    };
+    if let Err(err) = show_notification(&notification) {
+        _ = std::io::stderr().write_fmt(format_args!("[Starter] err={} | Show notification failed\n", err));
     }
+}
+
+fn apply_update(app_dir: &Path, profile: &str, notify: bool) -> Result<(), Box<dyn Error>> {
+    let starter = std::env::current_exe()?;
 
-    let m_main = jcall!(
-        runtime.env,
-        GetStaticMethodID,
-        c_main,
-        cstr!("main").as_ptr(),
-        cstr!("(Lcom/github/kr328/clash/StartupParameters;)V").as_ptr()
-    );
-    if m_main == null_mut() {
-        jcall!(runtime.env, ExceptionDescribe);
+    // Version being replaced is the resolved one, `current` pointer may be absent.
+    match apply_staged_update(&resolve_layout(app_dir, None)?, &starter)? {
+        SwapResult::Unchanged => {}
+        result => {
+            if notify {
+                notify_update_event(app_dir, profile, "Update installed", Urgency::Normal);
+            }
 
-        return Err("Invalid application package".into());
+            if let SwapResult::StarterReplaced(starter) = result {
+                return restart_starter(&starter);
+            }
+        }
     }
 
-    jcall!(
-        runtime.env,
-        CallStaticVoidMethod,
-        c_main,
-        m_main,
-        parameters.new_java_object(runtime.env)
-    );
-    if jcall!(runtime.env, ExceptionCheck) == JNI_TRUE {
-        jcall!(runtime.env, ExceptionDescribe);
+    match check_pending_update(app_dir, &starter)? {
+        SwapResult::Unchanged => {}
+        result => {
+            if notify {
+                notify_update_event(
+                    app_dir,
+                    profile,
+                    "Update rolled back after repeated launch failures",
+                    Urgency::Critical,
+                );
+            }
 
-        return Err("Unexpected exception".into());
+            if let SwapResult::StarterReplaced(starter) = result {
+                return restart_starter(&starter);
+            }
+        }
     }
 
     Ok(())
 }
 
+// Jar embedded into self-extracting starter is used until one is extracted next to it.
+fn select_classes_jar(layout: &InstallLayout, embedded_jar: Option<&Path>) -> PathBuf {
+    match embedded_jar {
+        Some(jar) if layout.version.is_none() && !layout.root.join(APP_JAR_NAME).exists() => jar.to_path_buf(),
+        _ => layout.root.join(APP_JAR_NAME),
+    }
+}
+
+// An install that can not even be resolved has nothing running from it.
+fn running_instance(
+    options: &Options,
+    app_dir: &Path,
+    embedded_jar: Option<&Path>,
+    portable_dir: Option<&Path>,
+) -> Result<Option<String>, Box<dyn Error>> {
+    let metadata =
+        match resolve_layout(app_dir, None).and_then(|layout| resolve_app_metadata(&select_classes_jar(&layout, embedded_jar))) {
+            Ok(metadata) => metadata,
+            Err(_) => return Ok(None),
+        };
+
+    running_profile(options, portable_dir, &metadata)
+}
+
+fn report_error(title: &str, err: &dyn Error) -> ! {
+    _ = std::io::stderr().write_fmt(format_args!("[Starter] err={} | Launch failed\n", err));
+    _ = std::io::stderr().flush();
+
+    if !ERROR_DIALOG.load(Ordering::Relaxed) {
+        exit(1)
+    }
+
+    let message = match error_log_file() {
+        Some(path) => format!("{}\n\nDetails: {}", err, path.display()),
+        None => err.to_string(),
+    };
+
+    #[cfg(windows)]
+    win32::ui::show_error_message(title, &message);
+
+    #[cfg(target_os = "linux")]
+    linux::ui::show_error_message(title, &message);
+
+    exit(1)
+}
+
 fn main() {
     let options = Options::parse();
 
-    if let Err(err) = run_app(&options) {
-        _ = std::io::stderr().write_fmt(format_args!("[Starter] err={} | Launch failed", err));
-        _ = std::io::stderr().flush();
+    // Only real launches of the current version count as launches of pending update.
+    let launches_app = options.command.is_none()
+        && options.stage_update.is_none()
+        && !options.install_desktop_entry
+        && !options.uninstall_desktop_entry;
+    let tracks_update = launches_app && options.use_version.is_none();
+    ERROR_DIALOG.store(launches_app && !options.headless, Ordering::Relaxed);
 
-        #[cfg(windows)]
-        win32::ui::show_error_message(&err.to_string());
+    #[cfg(windows)]
+    if !launches_app {
+        win32::redirect::attach_parent_console();
+    }
 
-        exit(1)
+    if let Some(Command::Bundle(bundle)) = &options.command {
+        if let Err(err) = create_bundle(bundle) {
+            report_error(DEFAULT_APP_NAME, &err.with_message("Create bundle"))
+        }
+
+        return;
+    }
+
+    let app_dir = current_app_dir()
+        .map_err(|e| e.with_message("App dir not found"))
+        .unwrap_or_else(|e| report_error(DEFAULT_APP_NAME, &e));
+
+    let portable_dir = resolve_portable_dir(&options, &app_dir);
+    let embedded = load_embedded_payload(portable_dir.as_deref())
+        .map_err(|e| e.with_message("Load embedded payload"))
+        .unwrap_or_else(|e| report_error(DEFAULT_APP_NAME, &e));
+    let app_dir = embedded.as_ref().map(|p| p.app_dir.clone()).unwrap_or(app_dir);
+
+    if let Some(source) = &options.stage_update {
+        if let Err(err) = resolve_layout(&app_dir, None).and_then(|layout| stage_update(&layout, source)) {
+            report_error(DEFAULT_APP_NAME, &err.with_message("Stage update"))
+        }
+
+        return;
+    }
+
+    let embedded_jar = embedded.and_then(|p| p.app_jar);
+
+    if tracks_update {
+        match running_instance(&options, &app_dir, embedded_jar.as_deref(), portable_dir.as_deref()) {
+            Ok(Some(profile)) if has_staged_update(&app_dir) => {
+                _ = std::io::stderr().write_fmt(format_args!(
+                    "[Starter] profile={} | Instance running, update deferred\n",
+                    profile
+                ));
+            }
+            Ok(Some(_)) => {}
+            Ok(None) => {
+                if let Err(err) = apply_update(&app_dir, options.profile_name(), options.hide_window && !options.headless) {
+                    report_error(DEFAULT_APP_NAME, &err.with_message("Apply update"))
+                }
+            }
+            Err(err) => report_error(DEFAULT_APP_NAME, &err.with_message("Check running instance")),
+        }
+    }
+
+    let layout = resolve_layout(&app_dir, options.use_version.as_deref())
+        .map_err(|e| e.with_message("Resolve install layout"))
+        .unwrap_or_else(|e| report_error(DEFAULT_APP_NAME, &e));
+    let classes_jar = select_classes_jar(&layout, embedded_jar.as_deref());
+    let metadata = resolve_app_metadata(&classes_jar)
+        .map_err(|e| e.with_message("Resolve app metadata"))
+        .unwrap_or_else(|e| {
+            if tracks_update {
+                let _ = record_launch_failure(&app_dir, &e.to_string());
+            }
+
+            report_error(DEFAULT_APP_NAME, &e)
+        });
+
+    if let Some(Command::Migrate(migrate)) = &options.command {
+        let result = resolve_app_dirs(&options, portable_dir.as_deref(), &metadata).and_then(|dirs| {
+            if !migrate.dry_run {
+                ensure_not_running(options.profile_name(), &dirs)?;
+            }
+
+            legacy_base_dir(&options, portable_dir.as_deref(), &metadata)
+                .map(|legacy_dir| legacy_dir.map(|legacy_dir| migrate_legacy_data(&legacy_dir, &dirs, migrate.dry_run)))
+        });
+
+        match result {
+            Ok(logs) => {
+                for line in logs.unwrap_or_default() {
+                    _ = std::io::stderr().write_fmt(format_args!("{}\n", line));
+                }
+            }
+            Err(err) => report_error(&metadata.name, &err.with_message("Migrate legacy data")),
+        }
+
+        return;
+    }
+
+    if let Some(Command::Profile(profile)) = &options.command {
+        if let Err(err) = manage_profiles(&options, profile, portable_dir.as_deref(), &metadata) {
+            report_error(&metadata.name, &err.with_message("Manage profiles"))
+        }
+
+        return;
+    }
+
+    if let Some(Command::Autostart(autostart)) = &options.command {
+        if let Err(err) = manage_autostart(&options, autostart, &metadata) {
+            report_error(&metadata.name, &err.with_message("Manage autostart"))
+        }
+
+        return;
+    }
+
+    if options.install_desktop_entry || options.uninstall_desktop_entry {
+        let result = if options.install_desktop_entry {
+            install_desktop_entry(&options, &layout, &metadata)
+        } else {
+            uninstall_desktop_entry(&options, &metadata)
+        };
+
+        if let Err(err) = result {
+            report_error(&metadata.name, &err.with_message("Manage desktop entry"))
+        }
+
+        return;
+    }
+
+    match run_app(&options, &layout, &classes_jar, &metadata, portable_dir.as_deref()) {
+        Ok(_) if tracks_update => {
+            let _ = confirm_update(&app_dir);
+        }
+        Ok(_) => {}
+        Err(err) => {
+            if tracks_update {
+                let _ = record_launch_failure(&app_dir, &err.to_string());
+            }
+
+            report_error(&metadata.name, err.as_ref())
+        }
     }
 }
@@ -0,0 +1,229 @@
+use std::error::Error;
+
+const CONTINUATION_MARK: u8 = b' ';
+
+#[derive(Default)]
+pub struct Attributes {
+    values: Vec<(String, String)>,
+}
+
+#[derive(Default)]
+pub struct Manifest {
+    pub main: Attributes,
+    pub entries: Vec<(String, Attributes)>,
+}
+
+impl Attributes {
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.values
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    // Later value overrides earlier one, the same as java.util.jar.Manifest.
+    fn put(&mut self, name: String, value: String) {
+        if let Some(pair) = self.values.iter_mut().find(|(key, _)| key.eq_ignore_ascii_case(&name)) {
+            pair.1 = value;
+        } else {
+            self.values.push((name, value));
+        }
+    }
+}
+
+impl Manifest {
+    fn entry_mut(&mut self, name: &str) -> &mut Attributes {
+        if let Some(idx) = self.entries.iter().position(|(key, _)| key == name) {
+            &mut self.entries[idx].1
+        } else {
+            self.entries.push((name.to_owned(), Attributes::default()));
+
+            &mut self.entries.last_mut().unwrap().1
+        }
+    }
+}
+
+fn split_lines(content: &[u8]) -> Vec<&[u8]> {
+    let mut lines = Vec::new();
+
+    let mut start = 0;
+    let mut idx = 0;
+    while idx < content.len() {
+        match content[idx] {
+            b'\r' => {
+                lines.push(&content[start..idx]);
+
+                if content.get(idx + 1) == Some(&b'\n') {
+                    idx += 1;
+                }
+
+                start = idx + 1;
+            }
+            b'\n' => {
+                lines.push(&content[start..idx]);
+
+                start = idx + 1;
+            }
+            _ => {}
+        }
+
+        idx += 1;
+    }
+
+    if start < content.len() {
+        lines.push(&content[start..]);
+    }
+
+    lines
+}
+
+// Joins continuation lines and groups headers into sections separated by blank lines.
+fn split_sections(content: &[u8]) -> Result<Vec<Vec<Vec<u8>>>, Box<dyn Error>> {
+    let mut sections: Vec<Vec<Vec<u8>>> = vec![Vec::new()];
+
+    for (idx, line) in split_lines(content).into_iter().enumerate() {
+        let section = sections.last_mut().unwrap();
+
+        if line.is_empty() {
+            if !section.is_empty() {
+                sections.push(Vec::new());
+            }
+        } else if line[0] == CONTINUATION_MARK {
+            match section.last_mut() {
+                Some(header) => header.extend_from_slice(&line[1..]),
+                None => return Err(format!("Unexpected continuation at manifest line {}", idx + 1).into()),
+            }
+        } else {
+            section.push(line.to_vec());
+        }
+    }
+
+    if sections.last().is_some_and(|s| s.is_empty()) {
+        sections.pop();
+    }
+
+    Ok(sections)
+}
+
+fn parse_header(header: Vec<u8>) -> Result<(String, String), Box<dyn Error>> {
+    let header = String::from_utf8(header).map_err(|_| "Invalid UTF-8 in manifest")?;
+
+    let (name, value) = header
+        .split_once(':')
+        .filter(|(name, _)| !name.is_empty() && name.bytes().all(|c| c.is_ascii_alphanumeric() || c == b'-' || c == b'_'))
+        .ok_or_else(|| format!("Invalid manifest header: {}", header))?;
+
+    Ok((name.to_owned(), value.strip_prefix(' ').unwrap_or(value).to_owned()))
+}
+
+pub fn parse_manifest(content: &[u8]) -> Result<Manifest, Box<dyn Error>> {
+    let mut manifest = Manifest::default();
+
+    for (idx, section) in split_sections(content)?.into_iter().enumerate() {
+        let headers = section.into_iter().map(parse_header).collect::<Result<Vec<_>, _>>()?;
+
+        let (attributes, headers) = if idx == 0 {
+            (&mut manifest.main, &headers[..])
+        } else {
+            let (name, value) = &headers[0];
+            if !name.eq_ignore_ascii_case("Name") {
+                return Err(format!("Manifest section starts with {} instead of Name", name).into());
+            }
+
+            (manifest.entry_mut(value), &headers[1..])
+        };
+
+        for (name, value) in headers {
+            attributes.put(name.to_owned(), value.to_owned());
+        }
+    }
+
+    Ok(manifest)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_manifest;
+
+    #[test]
+    fn joins_continuation_split_inside_multibyte_char() {
+        // Writers wrap at 72 bytes regardless of character boundaries.
+        let value = "配置".repeat(20);
+        let header = format!("Implementation-Title: {}", value);
+        let (head, tail) = header.as_bytes().split_at(71);
+        assert!(std::str::from_utf8(head).is_err());
+
+        let mut content = b"Manifest-Version: 1.0\r\n".to_vec();
+        content.extend_from_slice(head);
+        content.extend_from_slice(b"\r\n ");
+        content.extend_from_slice(tail);
+        content.extend_from_slice(b"\r\n\r\n");
+
+        let manifest = parse_manifest(&content).unwrap();
+        assert_eq!(manifest.main.get("Implementation-Title"), Some(value.as_str()));
+    }
+
+    #[test]
+    fn joins_class_path_wrapped_by_jar_tool() {
+        // Verbatim `META-INF/MANIFEST.MF` written by `jar --create --manifest` of JDK 17.0.15.
+        let content = include_bytes!("testdata/jar-17.MF");
+        let manifest = parse_manifest(content).unwrap();
+
+        assert_eq!(
+            manifest.main.get("Class-Path"),
+            Some(
+                "lib/kotlin-stdlib-1.9.22.jar lib/kotlinx-coroutines-core-jvm-1.8.0.jar \
+                 lib/annotations-23.0.0.jar lib/snakeyaml-engine-2.7.jar"
+            )
+        );
+        assert_eq!(manifest.main.get("Created-By"), Some("17.0.15 (Debian)"));
+    }
+
+    #[test]
+    fn accepts_any_line_ending() {
+        for content in [
+            &b"Manifest-Version: 1.0\r\nMain-Class: a.Main\r\n"[..],
+            b"Manifest-Version: 1.0\rMain-Class: a.Main\r",
+            b"Manifest-Version: 1.0\nMain-Class: a.Main",
+            b"Manifest-Version: 1.0\r\nMain-Class: a.\r\n Main",
+        ] {
+            let manifest = parse_manifest(content).unwrap();
+
+            assert_eq!(manifest.main.get("Manifest-Version"), Some("1.0"));
+            assert_eq!(manifest.main.get("main-class"), Some("a.Main"));
+            assert!(manifest.entries.is_empty());
+        }
+    }
+
+    #[test]
+    fn later_duplicate_wins() {
+        let manifest = parse_manifest(b"Main-Class: a.First\nmain-class: a.Second\n").unwrap();
+
+        assert_eq!(manifest.main.get("Main-Class"), Some("a.Second"));
+    }
+
+    #[test]
+    fn parses_entry_sections() {
+        let content = b"Manifest-Version: 1.0\n\nName: a/b.class\nSHA-256-Digest: one\n\n\nName: c/\nSealed: true\n\nName: a/b.class\nSHA-256-Digest: two\n";
+        let manifest = parse_manifest(content).unwrap();
+
+        assert_eq!(manifest.entries.len(), 2);
+        assert_eq!(manifest.entries[0].0, "a/b.class");
+        assert_eq!(manifest.entries[0].1.get("SHA-256-Digest"), Some("two"));
+        assert_eq!(manifest.entries[0].1.get("Name"), None);
+        assert_eq!(manifest.entries[1].0, "c/");
+        assert_eq!(manifest.entries[1].1.get("Sealed"), Some("true"));
+    }
+
+    #[test]
+    fn rejects_section_without_name() {
+        let err = parse_manifest(b"Manifest-Version: 1.0\n\nSealed: true\n").err().unwrap();
+
+        assert!(err.to_string().contains("instead of Name"));
+    }
+
+    #[test]
+    fn rejects_leading_continuation() {
+        assert!(parse_manifest(b" Manifest-Version: 1.0\n").is_err());
+    }
+}
@@ -0,0 +1,187 @@
+use std::{
+    cmp::Ordering,
+    error::Error,
+    fmt::{Display, Formatter},
+    io::{Read, Seek},
+    path::Path,
+};
+
+use crate::{
+    metadata::manifest::{parse_manifest, Attributes},
+    options::STARTER_VERSION,
+    utils::version::compare_versions,
+};
+
+pub mod manifest;
+
+pub const DEFAULT_APP_NAME: &str = "Clash for Desktop";
+pub const DEFAULT_APP_VERSION: &str = "unknown";
+
+#[derive(PartialEq, Eq)]
+pub enum Edition {
+    Foss,
+    Premium,
+    Other(String),
+}
+
+#[derive(PartialEq, Eq)]
+pub enum Channel {
+    Stable,
+    Beta,
+    Nightly,
+    Other(String),
+}
+
+pub struct Metadata {
+    pub name: String,
+    pub version: String,
+    pub build_id: Option<String>,
+    pub vendor: Option<String>,
+    pub channel: Channel,
+    pub edition: Edition,
+    pub minimum_starter_version: Option<String>,
+    pub class_path: Vec<String>,
+    pub main_module: Option<String>,
+    pub add_opens: Vec<String>,
+    pub add_exports: Vec<String>,
+    pub main_class: Option<String>,
+    pub entry_method: Option<String>,
+    pub entry_signature: Option<String>,
+}
+
+fn get_text(attributes: &Attributes, key: &str) -> Option<String> {
+    attributes
+        .get(key)
+        .map(|text| text.trim())
+        .filter(|text| !text.is_empty())
+        .map(|text| text.to_owned())
+}
+
+fn split_list(attributes: &Attributes, key: &str) -> Vec<String> {
+    attributes
+        .get(key)
+        .map(|text| text.split_whitespace().map(|s| s.to_owned()).collect())
+        .unwrap_or_default()
+}
+
+impl Edition {
+    fn from_attributes(attributes: &Attributes) -> Edition {
+        if let Some(edition) = get_text(attributes, "Clash-Edition") {
+            return match edition.to_ascii_lowercase().as_str() {
+                "foss" => Edition::Foss,
+                "premium" => Edition::Premium,
+                _ => Edition::Other(edition),
+            };
+        }
+
+        match get_text(attributes, "Clash-Premium") {
+            Some(text) if text == "true" => Edition::Premium,
+            _ => Edition::Foss,
+        }
+    }
+}
+
+impl Display for Edition {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Edition::Foss => f.write_str("foss"),
+            Edition::Premium => f.write_str("premium"),
+            Edition::Other(name) => f.write_str(name),
+        }
+    }
+}
+
+impl Channel {
+    fn from_attributes(attributes: &Attributes) -> Channel {
+        match get_text(attributes, "Clash-Channel") {
+            None => Channel::Stable,
+            Some(channel) => match channel.to_ascii_lowercase().as_str() {
+                "stable" => Channel::Stable,
+                "beta" => Channel::Beta,
+                "nightly" => Channel::Nightly,
+                _ => Channel::Other(channel),
+            },
+        }
+    }
+}
+
+impl Display for Channel {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Channel::Stable => f.write_str("stable"),
+            Channel::Beta => f.write_str("beta"),
+            Channel::Nightly => f.write_str("nightly"),
+            Channel::Other(name) => f.write_str(name),
+        }
+    }
+}
+
+impl Metadata {
+    fn new_from_attributes(attributes: &Attributes) -> Result<Metadata, Box<dyn Error>> {
+        Ok(Metadata {
+            name: get_text(attributes, "Implementation-Title").unwrap_or_else(|| DEFAULT_APP_NAME.to_owned()),
+            version: get_text(attributes, "Implementation-Version").unwrap_or_else(|| DEFAULT_APP_VERSION.to_owned()),
+            build_id: get_text(attributes, "Clash-Build-Id"),
+            vendor: get_text(attributes, "Implementation-Vendor"),
+            channel: Channel::from_attributes(attributes),
+            edition: Edition::from_attributes(attributes),
+            minimum_starter_version: get_text(attributes, "Clash-Minimum-Starter-Version"),
+            class_path: split_list(attributes, "Class-Path"),
+            main_module: get_text(attributes, "Clash-Main-Module"),
+            add_opens: split_list(attributes, "Add-Opens"),
+            add_exports: split_list(attributes, "Add-Exports"),
+            main_class: get_text(attributes, "Main-Class"),
+            entry_method: get_text(attributes, "Clash-Entry-Method"),
+            entry_signature: get_text(attributes, "Clash-Entry-Signature"),
+        })
+    }
+
+    pub fn check_starter_version(&self) -> Result<(), Box<dyn Error>> {
+        if let Some(version) = &self.minimum_starter_version {
+            if compare_versions(STARTER_VERSION, version) == Ordering::Less {
+                return Err(format!(
+                    "Starter {} is older than required {}, please reinstall",
+                    STARTER_VERSION, version
+                )
+                .into());
+            }
+        }
+
+        Ok(())
+    }
+}
+
+pub fn resolve_app_metadata(classpath: &Path) -> Result<Metadata, Box<dyn Error>> {
+    read_app_metadata(std::fs::File::open(classpath)?)
+}
+
+pub fn read_app_metadata<R: Read + Seek>(jar: R) -> Result<Metadata, Box<dyn Error>> {
+    let mut zip = zip::ZipArchive::new(jar)?;
+    let mut entry = zip.by_name("META-INF/MANIFEST.MF")?;
+
+    let mut content = Vec::with_capacity(entry.size() as usize);
+    entry.read_to_end(&mut content)?;
+
+    let manifest = parse_manifest(&content)?;
+
+    Metadata::new_from_attributes(&manifest.main)
+}
+
+impl Display for Metadata {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_fmt(format_args!(
+            "{} {} ({}, {}",
+            self.name, self.version, self.edition, self.channel
+        ))?;
+
+        if let Some(build_id) = &self.build_id {
+            f.write_fmt(format_args!(", build {}", build_id))?;
+        }
+
+        if let Some(vendor) = &self.vendor {
+            f.write_fmt(format_args!(", {}", vendor))?;
+        }
+
+        f.write_str(")")
+    }
+}
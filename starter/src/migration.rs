@@ -0,0 +1,130 @@
+use std::{
+    io,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    dirs::AppDirs,
+    utils::fs::{copy_path, remove_path},
+    INTEGRITY_CACHE_NAME,
+};
+
+const MIGRATION_MARKER_NAME: &str = "migrated";
+const MIGRATION_VERSION: u32 = 1;
+
+// Files the starter itself used to keep in the base directory.
+const STATE_FILES: &[&str] = &["app.log", "app.log.old", "app_err.log", "app_err.log.old"];
+const CACHE_FILES: &[&str] = &[INTEGRITY_CACHE_NAME];
+
+struct MigrationStep {
+    from: PathBuf,
+    to: PathBuf,
+}
+
+fn migrated_version(config_dir: &Path) -> u32 {
+    std::fs::read_to_string(config_dir.join(MIGRATION_MARKER_NAME))
+        .ok()
+        .and_then(|content| content.lines().find_map(|line| line.strip_prefix("version=")?.parse().ok()))
+        .unwrap_or(0)
+}
+
+// Steps run in order, sources of later steps are where earlier steps leave the files.
+fn plan_migration(legacy_dir: &Path, dirs: &AppDirs) -> Vec<MigrationStep> {
+    let mut steps = Vec::new();
+
+    let moves_base_dir = legacy_dir != dirs.config && legacy_dir.is_dir() && !dirs.config.exists();
+    if moves_base_dir {
+        steps.push(MigrationStep {
+            from: legacy_dir.to_path_buf(),
+            to: dirs.config.clone(),
+        });
+    }
+
+    let current_dir = if moves_base_dir { legacy_dir } else { &dirs.config };
+
+    for (names, target) in [(STATE_FILES, &dirs.state), (CACHE_FILES, &dirs.cache)] {
+        if *target == dirs.config {
+            continue;
+        }
+
+        for name in names.iter().filter(|name| current_dir.join(name).exists()) {
+            steps.push(MigrationStep {
+                from: dirs.config.join(name),
+                to: target.join(name),
+            });
+        }
+    }
+
+    steps
+}
+
+// Rename is atomic, copies across filesystems go through a temporary sibling first.
+fn move_path(from: &Path, to: &Path) -> io::Result<()> {
+    if let Some(parent) = to.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+
+    if std::fs::rename(from, to).is_ok() {
+        return Ok(());
+    }
+
+    let mut temp = to.to_path_buf().into_os_string();
+    temp.push(".migrating");
+    let temp = PathBuf::from(temp);
+
+    remove_path(&temp)?;
+    if let Err(err) = copy_path(from, &temp).and_then(|_| std::fs::rename(&temp, to)) {
+        let _ = remove_path(&temp);
+
+        return Err(err);
+    }
+
+    remove_path(from)
+}
+
+// Returns log lines, the launch goes on even if some steps failed and retries them next time.
+pub fn migrate_legacy_data(legacy_dir: &Path, dirs: &AppDirs, dry_run: bool) -> Vec<String> {
+    if migrated_version(&dirs.config) >= MIGRATION_VERSION {
+        return if dry_run {
+            vec![format!("[Starter] version={} | Migration already done", MIGRATION_VERSION)]
+        } else {
+            Vec::new()
+        };
+    }
+
+    let mut logs = Vec::new();
+    let mut failed = false;
+
+    for step in plan_migration(legacy_dir, dirs) {
+        let (from, to) = (step.from.display(), step.to.display());
+
+        if dry_run {
+            logs.push(format!("[Starter] from={} to={} | Would migrate", from, to));
+        } else if step.to.exists() {
+            logs.push(format!(
+                "[Starter] from={} to={} | Migration skipped, destination exists",
+                from, to
+            ));
+        } else if let Err(err) = move_path(&step.from, &step.to) {
+            logs.push(format!("[Starter] from={} to={} err={} | Migration failed", from, to, err));
+
+            failed = true;
+        } else {
+            logs.push(format!("[Starter] from={} to={} | Migrated", from, to));
+        }
+    }
+
+    if !dry_run && !failed {
+        let marker = std::fs::create_dir_all(&dirs.config).and_then(|_| {
+            std::fs::write(
+                dirs.config.join(MIGRATION_MARKER_NAME),
+                format!("version={}\n", MIGRATION_VERSION),
+            )
+        });
+        if let Err(err) = marker {
+            logs.push(format!("[Starter] err={} | Write migration marker failed", err));
+        }
+    }
+
+    logs
+}
@@ -0,0 +1,30 @@
+use std::error::Error;
+
+#[derive(Clone, Copy)]
+pub enum Urgency {
+    Low = 0,
+    Normal = 1,
+    Critical = 2,
+}
+
+#[cfg_attr(windows, allow(dead_code))]
+pub struct Notification<'a> {
+    pub app_name: &'a str,
+    // Desktop entry id, doubles as icon name once the desktop entry is installed. Empty if unknown.
+    pub app_id: &'a str,
+    pub summary: &'a str,
+    pub body: &'a str,
+    pub urgency: Urgency,
+}
+
+pub fn show_notification(notification: &Notification) -> Result<(), Box<dyn Error>> {
+    #[cfg(target_os = "linux")]
+    return crate::linux::notification::show_notification(notification);
+
+    #[cfg(windows)]
+    {
+        let _ = notification;
+
+        Err("Notifications are only supported on Linux".into())
+    }
+}
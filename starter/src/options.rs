@@ -1,9 +1,13 @@
-use clap::Parser;
+use std::path::PathBuf;
+
+use clap::{Args, Parser, Subcommand, ValueEnum};
+
+pub const STARTER_VERSION: &str = "1.0.0";
 
 #[derive(Parser)]
 #[command(name = "starter")]
 #[command(author = "Kr328")]
-#[command(version = "1.0.0")]
+#[command(version = STARTER_VERSION)]
 #[command(about = "Starter of Clash for Desktop.")]
 pub struct Options {
     #[arg(long, default_value = "")]
@@ -14,4 +18,132 @@ pub struct Options {
 
     #[arg(long, default_value_t = false)]
     pub hide_window: bool,
+
+    #[arg(long, default_value_t = false)]
+    pub portable: bool,
+
+    #[arg(long, value_name = "NAME")]
+    pub profile: Option<String>,
+
+    #[arg(long, default_value_t = false, conflicts_with = "install_desktop_entry")]
+    pub headless: bool,
+
+    #[arg(long, default_value_t = false, conflicts_with = "uninstall_desktop_entry")]
+    pub install_desktop_entry: bool,
+
+    #[arg(long, default_value_t = false)]
+    pub uninstall_desktop_entry: bool,
+
+    #[arg(long, value_name = "VERSION")]
+    pub use_version: Option<String>,
+
+    #[arg(long, value_name = "PATH_OR_URL")]
+    pub stage_update: Option<String>,
+
+    #[arg(value_name = "URL")]
+    pub url: Option<String>,
+
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// Assemble starter, app jar and runtime image into a bundle archive.
+    Bundle(BundleOptions),
+    /// Move data left in the legacy base directory into current locations.
+    Migrate(MigrateOptions),
+    /// Launch the app on login through an autostart entry or a systemd user unit.
+    Autostart(AutostartOptions),
+    /// Manage named profiles, each with its own directories, logs and instance.
+    Profile(ProfileOptions),
+}
+
+#[derive(Args)]
+pub struct ProfileOptions {
+    #[command(subcommand)]
+    pub action: ProfileAction,
+}
+
+#[derive(Subcommand)]
+pub enum ProfileAction {
+    /// List existing profiles.
+    List,
+    /// Create an empty profile.
+    Create { name: String },
+    /// Copy configuration and data of a profile into a new one.
+    Copy { from: String, to: String },
+    /// Delete a profile and all of its files.
+    Delete { name: String },
+}
+
+#[derive(Args)]
+pub struct AutostartOptions {
+    #[arg(value_enum)]
+    pub action: AutostartAction,
+
+    #[arg(long, value_enum)]
+    pub method: Option<AutostartMethod>,
+}
+
+#[derive(Clone, Copy, PartialEq, ValueEnum)]
+pub enum AutostartAction {
+    Enable,
+    Disable,
+    Status,
+}
+
+#[derive(Clone, Copy, PartialEq, ValueEnum)]
+pub enum AutostartMethod {
+    /// Desktop entry in the XDG autostart directory.
+    Desktop,
+    /// Service unit managed by `systemctl --user`.
+    Systemd,
+}
+
+impl Options {
+    pub fn profile_name(&self) -> &str {
+        self.profile.as_deref().unwrap_or(crate::dirs::DEFAULT_PROFILE_NAME)
+    }
+}
+
+#[derive(Args)]
+pub struct MigrateOptions {
+    #[arg(long, default_value_t = false)]
+    pub dry_run: bool,
+}
+
+#[derive(Args)]
+pub struct BundleOptions {
+    #[arg(long, value_name = "PATH")]
+    pub starter: Option<PathBuf>,
+
+    #[arg(long, value_name = "PATH")]
+    pub jar: Option<PathBuf>,
+
+    #[arg(long, value_name = "PATH")]
+    pub runtime: PathBuf,
+
+    #[arg(long, value_name = "PATH")]
+    pub lib: Option<PathBuf>,
+
+    #[arg(long, value_name = "PATH")]
+    pub icons: Option<PathBuf>,
+
+    #[arg(long, value_name = "PATH", default_value = "bundle.zip")]
+    pub output: PathBuf,
+
+    #[arg(long, value_enum)]
+    pub format: Option<BundleFormat>,
+}
+
+#[derive(Clone, Copy, PartialEq, ValueEnum)]
+pub enum BundleFormat {
+    Zip,
+    #[value(name = "tar.gz")]
+    TarGz,
+    #[value(name = "tar.zst")]
+    TarZst,
+    /// Starter with runtime and jar appended, extracted to cache directory on first run.
+    SelfExtracting,
 }
@@ -0,0 +1,317 @@
+use std::{
+    error::Error,
+    fs::File,
+    io::{self, Cursor, Read, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+    sync::OnceLock,
+};
+
+use sha2::{Digest, Sha256};
+
+use crate::{
+    archive::unpack_tar_zst,
+    dirs::default_cache_dir,
+    integrity::hash_file,
+    metadata::{read_app_metadata, Edition},
+    signature::{signature_path, verify_embedded_signature},
+    utils::strings::encode_hex,
+    APP_JAR_NAME,
+};
+
+const PAYLOAD_MAGIC: &[u8; 8] = b"CLSHPAYL";
+const PAYLOADS_DIR_NAME: &str = "payloads";
+const PAYLOAD_LOCK_NAME: &str = ".lock";
+const EXTRACTING_INFIX: &str = ".extracting-";
+const REMOVING_INFIX: &str = ".removing-";
+
+// Shared lock on the payload directory in use, held until process exits.
+static PAYLOAD_LOCK: OnceLock<File> = OnceLock::new();
+pub const PAYLOAD_ARCHIVE_NAME: &str = "payload.tar.zst";
+pub const APP_JAR_SIGNATURE_NAME: &str = "clash-multiplatform.jar.sig";
+
+// Self-extracting executable: <starter><payload><trailer>
+// Payload: <tar.zst archive><raw entries><index><index length, u64 le>
+// Index line: "<offset> <length> <sha256> <name>", offset is relative to payload start.
+// Raw entries are the app jar and its signature, if any.
+// Trailer: <sha256 of payload, 32 bytes><payload offset, u64 le><magic>
+const TRAILER_SIZE: u64 = 32 + 8 + PAYLOAD_MAGIC.len() as u64;
+
+pub struct PayloadTrailer {
+    pub hash: [u8; 32],
+    pub offset: u64,
+    pub length: u64,
+}
+
+pub struct IndexEntry {
+    pub name: String,
+    pub offset: u64,
+    pub length: u64,
+    pub hash: String,
+}
+
+pub struct EmbeddedPayload {
+    pub app_dir: PathBuf,
+    pub app_jar: Option<PathBuf>,
+}
+
+pub fn write_trailer<W: Write>(writer: &mut W, hash: &[u8; 32], offset: u64) -> io::Result<()> {
+    writer.write_all(hash)?;
+    writer.write_all(&offset.to_le_bytes())?;
+    writer.write_all(PAYLOAD_MAGIC)
+}
+
+pub fn read_trailer(file: &mut File) -> io::Result<Option<PayloadTrailer>> {
+    let size = file.metadata()?.len();
+    if size < TRAILER_SIZE {
+        return Ok(None);
+    }
+
+    let mut trailer = [0u8; TRAILER_SIZE as usize];
+    file.seek(SeekFrom::Start(size - TRAILER_SIZE))?;
+    file.read_exact(&mut trailer)?;
+
+    if &trailer[40..] != PAYLOAD_MAGIC {
+        return Ok(None);
+    }
+
+    let hash: [u8; 32] = trailer[..32].try_into().unwrap();
+    let offset = u64::from_le_bytes(trailer[32..40].try_into().unwrap());
+    if offset > size - TRAILER_SIZE {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "Invalid payload offset"));
+    }
+
+    Ok(Some(PayloadTrailer {
+        hash,
+        offset,
+        length: size - TRAILER_SIZE - offset,
+    }))
+}
+
+pub fn write_index<W: Write>(writer: &mut W, index: &[IndexEntry]) -> io::Result<()> {
+    let content = index
+        .iter()
+        .map(|e| format!("{} {} {} {}\n", e.offset, e.length, e.hash, e.name))
+        .collect::<String>();
+
+    writer.write_all(content.as_bytes())?;
+    writer.write_all(&(content.len() as u64).to_le_bytes())
+}
+
+pub fn read_index(file: &mut File, trailer: &PayloadTrailer) -> io::Result<Vec<IndexEntry>> {
+    let invalid = || io::Error::new(io::ErrorKind::InvalidData, "Invalid payload index");
+
+    let mut length = [0u8; 8];
+    file.seek(SeekFrom::Start(
+        trailer.offset + trailer.length.checked_sub(8).ok_or_else(invalid)?,
+    ))?;
+    file.read_exact(&mut length)?;
+
+    let length = u64::from_le_bytes(length);
+    let start = (trailer.length - 8).checked_sub(length).ok_or_else(invalid)?;
+
+    let mut content = String::new();
+    file.seek(SeekFrom::Start(trailer.offset + start))?;
+    file.take(length).read_to_string(&mut content)?;
+
+    content
+        .lines()
+        .map(|line| {
+            let mut segments = line.splitn(4, ' ');
+            let offset: u64 = segments.next()?.parse().ok()?;
+            let length: u64 = segments.next()?.parse().ok()?;
+            let hash = segments.next()?.to_owned();
+            let name = segments.next()?.to_owned();
+
+            (offset.checked_add(length)? <= start).then_some(IndexEntry {
+                name,
+                offset,
+                length,
+                hash,
+            })
+        })
+        .collect::<Option<Vec<_>>>()
+        .ok_or_else(invalid)
+}
+
+pub fn hash_region(file: &mut File, offset: u64, length: u64) -> io::Result<[u8; 32]> {
+    let mut hasher = Sha256::new();
+
+    file.seek(SeekFrom::Start(offset))?;
+    io::copy(&mut file.take(length), &mut hasher)?;
+
+    Ok(hasher.finalize().into())
+}
+
+fn extract_payload(file: &mut File, trailer: &PayloadTrailer, archive: &IndexEntry, dest: &Path) -> Result<(), Box<dyn Error>> {
+    if hash_region(file, trailer.offset, trailer.length)? != trailer.hash {
+        return Err("Embedded payload corrupted, please download again.".into());
+    }
+
+    let mut extracting = dest.to_path_buf().into_os_string();
+    extracting.push(format!("{}{}", EXTRACTING_INFIX, std::process::id()));
+    let extracting = PathBuf::from(extracting);
+
+    let _ = std::fs::remove_dir_all(&extracting);
+    std::fs::create_dir_all(&extracting)?;
+
+    file.seek(SeekFrom::Start(trailer.offset + archive.offset))?;
+    if let Err(err) = unpack_tar_zst(file.take(archive.length), &extracting) {
+        let _ = std::fs::remove_dir_all(&extracting);
+
+        return Err(err);
+    }
+
+    // Another instance may have finished extracting first.
+    if std::fs::rename(&extracting, dest).is_err() {
+        let _ = std::fs::remove_dir_all(&extracting);
+
+        if !dest.is_dir() {
+            return Err(format!("Unable to extract payload into {}", dest.display()).into());
+        }
+    }
+
+    Ok(())
+}
+
+fn read_entry(file: &mut File, trailer: &PayloadTrailer, entry: &IndexEntry) -> Result<Vec<u8>, Box<dyn Error>> {
+    let mut content = Vec::with_capacity(entry.length as usize);
+
+    file.seek(SeekFrom::Start(trailer.offset + entry.offset))?;
+    file.take(entry.length).read_to_end(&mut content)?;
+
+    if encode_hex(&Sha256::digest(&content)) != entry.hash {
+        return Err(format!("Embedded {} corrupted, please download again.", entry.name).into());
+    }
+
+    Ok(content)
+}
+
+fn serve_app_jar(content: &[u8], hash: &str, signature: Option<&[u8]>, app_dir: &Path) -> Result<PathBuf, Box<dyn Error>> {
+    // Module path only accepts files named *.jar, so memfd serves class path launches only.
+    #[cfg(target_os = "linux")]
+    if let Ok(path) = crate::linux::memfd::create_sealed_file(cstr::cstr!("clash-multiplatform.jar"), content) {
+        if crate::metadata::resolve_app_metadata(&path).is_ok_and(|m| m.main_module.is_none()) {
+            return Ok(path);
+        }
+    }
+
+    // Copy lives in the payload directory named after payload hash, so it never goes stale.
+    let path = app_dir.join(APP_JAR_NAME);
+    if let Some(signature) = signature {
+        std::fs::write(signature_path(&path), signature)?;
+    }
+    if !hash_file(&path).is_ok_and(|h| h == hash) {
+        let temp = app_dir.join(format!("{}.{}", APP_JAR_NAME, std::process::id()));
+
+        std::fs::write(&temp, content)?;
+        std::fs::rename(&temp, &path)?;
+    }
+
+    Ok(path)
+}
+
+fn lock_payload(app_dir: &Path) -> io::Result<()> {
+    let lock = File::options()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(app_dir.join(PAYLOAD_LOCK_NAME))?;
+    lock.lock_shared()?;
+
+    let _ = PAYLOAD_LOCK.set(lock);
+
+    Ok(())
+}
+
+// Directories of other payloads are left to the instances still using them.
+fn remove_unused_payloads(payloads_dir: &Path, current: &Path) -> io::Result<()> {
+    for entry in std::fs::read_dir(payloads_dir)? {
+        let path = entry?.path();
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        if path == current || name.contains(EXTRACTING_INFIX) {
+            continue;
+        }
+
+        if !name.contains(REMOVING_INFIX) {
+            let in_use = File::options()
+                .create(true)
+                .truncate(false)
+                .write(true)
+                .open(path.join(PAYLOAD_LOCK_NAME))
+                .map_or(true, |lock| lock.try_lock().is_err());
+            if in_use {
+                continue;
+            }
+        }
+
+        // Renamed first, so a partially removed directory is never taken as extracted.
+        let mut removing = path.as_os_str().to_owned();
+        if !name.contains(REMOVING_INFIX) {
+            removing.push(format!("{}{}", REMOVING_INFIX, std::process::id()));
+            std::fs::rename(&path, &removing)?;
+        }
+        std::fs::remove_dir_all(&removing)?;
+    }
+
+    Ok(())
+}
+
+// Extracts the runtime into cache directory once, app jar is served from the executable on every launch.
+pub fn load_embedded_payload(portable_dir: Option<&Path>) -> Result<Option<EmbeddedPayload>, Box<dyn Error>> {
+    let mut file = File::open(std::env::current_exe()?)?;
+
+    let trailer = match read_trailer(&mut file)? {
+        Some(trailer) => trailer,
+        None => return Ok(None),
+    };
+    let index = read_index(&mut file, &trailer)?;
+
+    let jar = match index.iter().find(|e| e.name == APP_JAR_NAME) {
+        Some(entry) => {
+            let content = read_entry(&mut file, &trailer, entry)?;
+            let signature = match index.iter().find(|e| e.name == APP_JAR_SIGNATURE_NAME) {
+                Some(entry) => Some(read_entry(&mut file, &trailer, entry)?),
+                None => None,
+            };
+
+            // Served jar has no signature file next to it, so it is verified here.
+            verify_embedded_signature(APP_JAR_NAME, &content, signature.as_deref())?;
+
+            Some((content, &entry.hash, signature))
+        }
+        None => None,
+    };
+
+    // Cache directory belongs to the edition, like every other app directory.
+    let edition = match &jar {
+        Some((content, _, _)) => read_app_metadata(Cursor::new(content))?.edition,
+        None => Edition::Foss,
+    };
+    let payloads_dir = default_cache_dir(portable_dir, &edition)?.join(PAYLOADS_DIR_NAME);
+    let app_dir = payloads_dir.join(&encode_hex(&trailer.hash)[..16]);
+    if !app_dir.is_dir() {
+        let archive = index
+            .iter()
+            .find(|e| e.name == PAYLOAD_ARCHIVE_NAME)
+            .ok_or("Embedded payload archive missing")?;
+
+        std::fs::create_dir_all(&payloads_dir)?;
+
+        extract_payload(&mut file, &trailer, archive, &app_dir)?;
+        lock_payload(&app_dir)?;
+
+        // Every update ships a new payload, the ones it replaced would pile up otherwise.
+        if let Err(err) = remove_unused_payloads(&payloads_dir, &app_dir) {
+            _ = std::io::stderr().write_fmt(format_args!("[Starter] err={} | Remove old payloads failed\n", err));
+        }
+    } else {
+        lock_payload(&app_dir)?;
+    }
+
+    let app_jar = match jar {
+        Some((content, hash, signature)) => Some(serve_app_jar(&content, hash, signature.as_deref(), &app_dir)?),
+        None => None,
+    };
+
+    Ok(Some(EmbeddedPayload { app_dir, app_jar }))
+}
@@ -0,0 +1,158 @@
+use std::{
+    error::Error,
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    dirs::{list_profiles, resolve_profile_dirs, AppDirs, DEFAULT_PROFILE_NAME},
+    instance::is_instance_running,
+    metadata::Metadata,
+    options::{Options, ProfileAction, ProfileOptions},
+    utils::fs::{copy_path, remove_path},
+};
+
+pub fn ensure_not_running(name: &str, dirs: &AppDirs) -> Result<(), Box<dyn Error>> {
+    if is_instance_running(&dirs.state)? {
+        return Err(format!("Profile {} is running", name).into());
+    }
+
+    Ok(())
+}
+
+// Copies land in temporary siblings first, so a failed copy never leaves a half populated profile.
+fn copy_profile(from: &AppDirs, to: &AppDirs) -> Result<(), Box<dyn Error>> {
+    let mut pairs: Vec<(&Path, &Path)> = Vec::new();
+    for pair in [(&from.config, &to.config), (&from.data, &to.data)] {
+        if pair.0.exists() && !pairs.iter().any(|(source, _)| *source == pair.0.as_path()) {
+            pairs.push((pair.0, pair.1));
+        }
+    }
+
+    let temps = pairs
+        .iter()
+        .map(|(_, target)| {
+            let mut temp = target.as_os_str().to_os_string();
+            temp.push(".copying");
+
+            PathBuf::from(temp)
+        })
+        .collect::<Vec<_>>();
+
+    let result = pairs.iter().zip(&temps).try_for_each(|((source, _), temp)| {
+        remove_path(temp)?;
+        copy_path(source, temp)
+    });
+    if let Err(err) = result {
+        temps.iter().for_each(|temp| _ = remove_path(temp));
+
+        return Err(err.into());
+    }
+
+    for ((_, target), temp) in pairs.iter().zip(&temps) {
+        std::fs::rename(temp, target)?;
+    }
+
+    Ok(())
+}
+
+// Every profile runs from the same install, files of it can only be replaced while none of them is running.
+pub fn running_profile(
+    options: &Options,
+    portable_dir: Option<&Path>,
+    metadata: &Metadata,
+) -> Result<Option<String>, Box<dyn Error>> {
+    let default_dirs = resolve_profile_dirs(options, portable_dir, metadata, DEFAULT_PROFILE_NAME)?;
+
+    for name in std::iter::once(DEFAULT_PROFILE_NAME.to_owned()).chain(list_profiles(&default_dirs)) {
+        if is_instance_running(&resolve_profile_dirs(options, portable_dir, metadata, &name)?.state)? {
+            return Ok(Some(name));
+        }
+    }
+
+    Ok(None)
+}
+
+pub fn manage_profiles(
+    options: &Options,
+    profile: &ProfileOptions,
+    portable_dir: Option<&Path>,
+    metadata: &Metadata,
+) -> Result<(), Box<dyn Error>> {
+    let resolve = |name: &str| resolve_profile_dirs(options, portable_dir, metadata, name);
+
+    match &profile.action {
+        ProfileAction::List => {
+            let default_dirs = resolve(DEFAULT_PROFILE_NAME)?;
+            let profiles = std::iter::once(DEFAULT_PROFILE_NAME.to_owned()).chain(list_profiles(&default_dirs));
+
+            // Listing is the whole point of the command, output that can not be written fails it.
+            let mut stdout = std::io::stdout().lock();
+            for name in profiles {
+                let dirs = resolve(&name)?;
+
+                stdout.write_fmt(format_args!(
+                    "{} running={} path={}\n",
+                    name,
+                    is_instance_running(&dirs.state)?,
+                    dirs.config.display()
+                ))?;
+            }
+            stdout.flush()?;
+        }
+        ProfileAction::Create { name } => {
+            let dirs = resolve(name)?;
+            if dirs.config.exists() {
+                return Err(format!("Profile {} already exists", name).into());
+            }
+
+            for dir in dirs.unique() {
+                std::fs::create_dir_all(dir)?;
+            }
+
+            _ = std::io::stderr().write_fmt(format_args!(
+                "[Starter] profile={} path={} | Profile created\n",
+                name,
+                dirs.config.display()
+            ));
+        }
+        ProfileAction::Copy { from, to } => {
+            let (source, target) = (resolve(from)?, resolve(to)?);
+            if !source.config.exists() {
+                return Err(format!("Profile {} not found", from).into());
+            }
+            if target.unique().iter().any(|dir| dir.exists()) {
+                return Err(format!("Profile {} already exists", to).into());
+            }
+
+            ensure_not_running(from, &source)?;
+            copy_profile(&source, &target)?;
+
+            _ = std::io::stderr().write_fmt(format_args!(
+                "[Starter] from={} to={} path={} | Profile copied\n",
+                from,
+                to,
+                target.config.display()
+            ));
+        }
+        ProfileAction::Delete { name } => {
+            if name == DEFAULT_PROFILE_NAME {
+                return Err("Default profile can not be deleted".into());
+            }
+
+            let dirs = resolve(name)?;
+            if !dirs.config.exists() {
+                return Err(format!("Profile {} not found", name).into());
+            }
+
+            ensure_not_running(name, &dirs)?;
+            for dir in dirs.unique() {
+                remove_path(dir)?;
+            }
+
+            _ = std::io::stderr().write_fmt(format_args!("[Starter] profile={} | Profile deleted\n", name));
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,185 @@
+use std::{
+    error::Error,
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use ed25519_dalek::{Signature, VerifyingKey, PUBLIC_KEY_LENGTH, SIGNATURE_LENGTH};
+
+use crate::utils::strings::decode_hex;
+
+pub const SIGNATURE_SUFFIX: &str = ".sig";
+
+// Hex encoded ed25519 public key, pinned at build time.
+const PINNED_PUBLIC_KEY: Option<&str> = option_env!("CLASH_STARTER_PUBLIC_KEY");
+
+// "enforce" (default) refuses to launch on mismatch, "warn" only logs it.
+const SIGNATURE_POLICY: Option<&str> = option_env!("CLASH_STARTER_SIGNATURE_POLICY");
+
+fn pinned_public_key() -> Result<Option<VerifyingKey>, Box<dyn Error>> {
+    let key = match PINNED_PUBLIC_KEY {
+        Some(key) if !key.trim().is_empty() => key,
+        _ => return Ok(None),
+    };
+
+    let key: [u8; PUBLIC_KEY_LENGTH] = decode_hex(key)
+        .and_then(|key| key.try_into().ok())
+        .ok_or("Invalid pinned public key")?;
+
+    Ok(Some(VerifyingKey::from_bytes(&key)?))
+}
+
+// Accepts both raw and hex encoded signatures.
+fn parse_signature(content: &[u8], name: &str) -> Result<Signature, Box<dyn Error>> {
+    let bytes: [u8; SIGNATURE_LENGTH] = if content.len() == SIGNATURE_LENGTH {
+        content.try_into().unwrap()
+    } else {
+        std::str::from_utf8(content)
+            .ok()
+            .and_then(decode_hex)
+            .and_then(|bytes| bytes.try_into().ok())
+            .ok_or_else(|| format!("Invalid signature of {}", name))?
+    };
+
+    Ok(Signature::from_bytes(&bytes))
+}
+
+pub fn signature_path(path: &Path) -> PathBuf {
+    let mut signature_path = path.as_os_str().to_owned();
+    signature_path.push(SIGNATURE_SUFFIX);
+
+    PathBuf::from(signature_path)
+}
+
+fn verify_content(key: &VerifyingKey, name: &str, content: &[u8], signature: Option<&[u8]>) -> Result<(), Box<dyn Error>> {
+    let signature = parse_signature(signature.ok_or_else(|| format!("{} is not signed", name))?, name)?;
+
+    key.verify_strict(content, &signature)
+        .map_err(|_| format!("Signature of {} does not match", name).into())
+}
+
+fn verify_file(key: &VerifyingKey, path: &Path) -> Result<(), Box<dyn Error>> {
+    if !path.exists() {
+        return Err(format!("{} is missing", path.display()).into());
+    }
+
+    let signature_path = signature_path(path);
+    let signature = if signature_path.exists() {
+        Some(std::fs::read(&signature_path)?)
+    } else {
+        None
+    };
+
+    verify_content(key, &path.display().to_string(), &std::fs::read(path)?, signature.as_deref())
+}
+
+fn enforce_policy(result: Result<(), Box<dyn Error>>, policy: Option<&str>) -> Result<(), Box<dyn Error>> {
+    match result {
+        Err(err) if policy == Some("warn") => {
+            _ = std::io::stderr().write_fmt(format_args!("[Starter] err={} | Signature ignored\n", err));
+
+            Ok(())
+        }
+        Err(err) => Err(format!("{}, the application may have been tampered with", err).into()),
+        Ok(()) => Ok(()),
+    }
+}
+
+// With a pinned key every file must exist and be signed, otherwise removing it would turn off the check.
+pub fn verify_signatures(files: &[&Path]) -> Result<(), Box<dyn Error>> {
+    let key = match pinned_public_key()? {
+        Some(key) => key,
+        None => return Ok(()),
+    };
+
+    for file in files {
+        enforce_policy(verify_file(&key, file), SIGNATURE_POLICY)?;
+    }
+
+    Ok(())
+}
+
+// For content that is not backed by a regular file, e.g. jar embedded into self-extracting starter.
+pub fn verify_embedded_signature(name: &str, content: &[u8], signature: Option<&[u8]>) -> Result<(), Box<dyn Error>> {
+    match pinned_public_key()? {
+        Some(key) => enforce_policy(verify_content(&key, name, content, signature), SIGNATURE_POLICY),
+        None => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use ed25519_dalek::{Signer, SigningKey};
+
+    use super::{enforce_policy, signature_path, verify_file};
+    use crate::utils::strings::encode_hex;
+
+    fn temp_file(name: &str, content: &[u8]) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("starter-test-{}-{}", name, std::process::id()));
+
+        std::fs::write(&path, content).unwrap();
+        let _ = std::fs::remove_file(signature_path(&path));
+
+        path
+    }
+
+    #[test]
+    fn verifies_raw_and_hex_signatures() {
+        let key = SigningKey::from_bytes(&[7; 32]);
+        let path = temp_file("signed", b"content");
+        let signature = key.sign(b"content").to_bytes();
+
+        std::fs::write(signature_path(&path), signature).unwrap();
+        verify_file(&key.verifying_key(), &path).unwrap();
+
+        std::fs::write(signature_path(&path), encode_hex(&signature) + "\n").unwrap();
+        verify_file(&key.verifying_key(), &path).unwrap();
+
+        std::fs::remove_file(signature_path(&path)).unwrap();
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn rejects_missing_and_wrong_signatures() {
+        let key = SigningKey::from_bytes(&[7; 32]);
+        let path = temp_file("unsigned", b"content");
+
+        let err = verify_file(&key.verifying_key(), &path).err().unwrap();
+        assert!(err.to_string().ends_with("is not signed"), "{}", err);
+
+        let other_key = SigningKey::from_bytes(&[8; 32]);
+        std::fs::write(signature_path(&path), other_key.sign(b"content").to_bytes()).unwrap();
+        let err = verify_file(&key.verifying_key(), &path).err().unwrap();
+        assert!(err.to_string().starts_with("Signature of"), "{}", err);
+
+        std::fs::write(signature_path(&path), key.sign(b"tampered").to_bytes()).unwrap();
+        assert!(verify_file(&key.verifying_key(), &path).is_err());
+
+        std::fs::write(signature_path(&path), b"not a signature").unwrap();
+        let err = verify_file(&key.verifying_key(), &path).err().unwrap();
+        assert!(err.to_string().starts_with("Invalid signature"), "{}", err);
+
+        std::fs::remove_file(signature_path(&path)).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let err = verify_file(&key.verifying_key(), &path).err().unwrap();
+        assert!(err.to_string().ends_with("is missing"), "{}", err);
+    }
+
+    #[test]
+    fn warn_policy_only_logs() {
+        assert!(enforce_policy(Ok(()), None).is_ok());
+        assert!(enforce_policy(Ok(()), Some("warn")).is_ok());
+        assert!(enforce_policy(Err("Signature of a does not match".into()), Some("warn")).is_ok());
+
+        for policy in [None, Some("enforce")] {
+            let err = enforce_policy(Err("Signature of a does not match".into()), policy)
+                .err()
+                .unwrap();
+
+            assert!(err.to_string().contains("tampered"), "{}", err);
+        }
+    }
+}
@@ -1,14 +1,14 @@
-use std::{env::current_exe, error::Error, ptr::null_mut};
+use std::{env::current_exe, error::Error, ffi::CStr};
 
 use cstr::cstr;
-use jni_sys::{jint, jobject, jsize, JNIEnv, JNI_FALSE, JNI_TRUE};
+use jni_sys::{jboolean, jclass, jint, jobject, JNIEnv, JNI_FALSE, JNI_TRUE};
 
 use crate::{
-    dirs::default_base_dir,
-    metadata::Metadata,
+    deeplink::{new_java_action_map, DeepLink},
+    dirs::AppDirs,
     options::Options,
     utils::{
-        java::{jcall, JStringExt},
+        java::{jcall, new_java_string_array, JStringExt},
         strings::PathExt,
     },
 };
@@ -19,52 +19,85 @@ pub struct StartupParameters {
     pub hide_window: bool,
     pub starter: String,
     pub starter_arguments: Vec<String>,
+    pub portable: bool,
+    pub profile: String,
+    pub data_directory: String,
+    pub state_directory: String,
+    pub cache_directory: String,
+    pub deep_link: Option<DeepLink>,
+    pub headless: bool,
+    pub display_server: String,
+}
+
+// Fields added after the constructor signature was settled, apps opt in by declaring them.
+fn set_optional_boolean_field(env: *mut JNIEnv, class: jclass, object: jobject, name: &CStr, value: bool) {
+    let field = jcall!(env, GetFieldID, class, name.as_ptr(), cstr!("Z").as_ptr());
+    if field.is_null() {
+        jcall!(env, ExceptionClear);
+
+        return;
+    }
+
+    jcall!(env, SetBooleanField, object, field, value as jboolean);
+}
+
+fn set_optional_string_field(env: *mut JNIEnv, class: jclass, object: jobject, name: &CStr, value: &String) {
+    let field = jcall!(env, GetFieldID, class, name.as_ptr(), cstr!("Ljava/lang/String;").as_ptr());
+    if field.is_null() {
+        jcall!(env, ExceptionClear);
+
+        return;
+    }
+
+    jcall!(env, SetObjectField, object, field, value.to_java_string(env));
+}
+
+fn set_optional_map_field(env: *mut JNIEnv, class: jclass, object: jobject, name: &CStr, value: &[(&str, String)]) {
+    let field = jcall!(env, GetFieldID, class, name.as_ptr(), cstr!("Ljava/util/Map;").as_ptr());
+    if field.is_null() {
+        jcall!(env, ExceptionClear);
+
+        return;
+    }
+
+    jcall!(env, SetObjectField, object, field, new_java_action_map(env, value));
 }
 
 impl StartupParameters {
-    pub fn new(options: &Options, metadata: &Metadata) -> Result<Self, Box<dyn Error>> {
-        let base_directory = if options.base_directory.is_empty() {
-            default_base_dir(metadata)?.to_string_without_extend_length_mark()
-        } else {
-            options.base_directory.to_owned()
-        };
+    pub fn new(options: &Options, dirs: &AppDirs, portable: bool, deep_link: Option<DeepLink>) -> Result<Self, Box<dyn Error>> {
         let starter = current_exe()?.to_string_without_extend_length_mark();
         let starter_arguments = std::env::args().skip(1).collect::<Vec<_>>();
 
-        return Ok(StartupParameters {
-            base_directory,
+        Ok(StartupParameters {
+            base_directory: dirs.config.to_string_without_extend_length_mark(),
             no_shortcut: options.no_shortcut,
             hide_window: options.hide_window,
             starter,
             starter_arguments,
-        });
+            portable,
+            profile: options.profile_name().to_owned(),
+            data_directory: dirs.data.to_string_without_extend_length_mark(),
+            state_directory: dirs.state.to_string_without_extend_length_mark(),
+            cache_directory: dirs.cache.to_string_without_extend_length_mark(),
+            deep_link,
+            headless: options.headless,
+            display_server: String::new(),
+        })
     }
 
-    pub fn new_java_object(&self, env: *mut JNIEnv) -> jobject {
+    pub fn new_java_object(&self, env: *mut JNIEnv, class_name: &CStr) -> Result<jobject, Box<dyn Error>> {
         let base_directory = self.base_directory.to_java_string(env);
         let no_shortcut = if self.no_shortcut { JNI_TRUE } else { JNI_FALSE };
         let hide_window = if self.hide_window { JNI_TRUE } else { JNI_FALSE };
         let starter = self.starter.to_java_string(env);
-        let c_string = jcall!(env, FindClass, cstr!("java/lang/String").as_ptr());
-        let starter_arguments = jcall!(
-            env,
-            NewObjectArray,
-            self.starter_arguments.len() as jsize,
-            c_string,
-            null_mut()
-        );
+        let starter_arguments = new_java_string_array(env, &self.starter_arguments);
 
-        for (idx, value) in self.starter_arguments.iter().enumerate() {
-            jcall!(
-                env,
-                SetObjectArrayElement,
-                starter_arguments,
-                idx as jsize,
-                value.to_java_string(env)
-            );
-        }
+        let class = jcall!(env, FindClass, class_name.as_ptr());
+        if class.is_null() {
+            jcall!(env, ExceptionDescribe);
 
-        let class = jcall!(env, FindClass, cstr!("com/github/kr328/clash/StartupParameters").as_ptr());
+            return Err(format!("Class {} not found", class_name.to_string_lossy()).into());
+        }
 
         let constructor = jcall!(
             env,
@@ -73,8 +106,13 @@ impl StartupParameters {
             cstr!("<init>").as_ptr(),
             cstr!("(Ljava/lang/String;ZZLjava/lang/String;[Ljava/lang/String;)V").as_ptr()
         );
+        if constructor.is_null() {
+            jcall!(env, ExceptionDescribe);
+
+            return Err(format!("Class {} has no compatible constructor", class_name.to_string_lossy()).into());
+        }
 
-        jcall!(
+        let object = jcall!(
             env,
             NewObject,
             class,
@@ -84,6 +122,25 @@ impl StartupParameters {
             hide_window as jint,
             starter,
             starter_arguments
-        )
+        );
+        if object.is_null() {
+            jcall!(env, ExceptionDescribe);
+
+            return Err(format!("Unable to create {}", class_name.to_string_lossy()).into());
+        }
+
+        set_optional_boolean_field(env, class, object, cstr!("portable"), self.portable);
+        set_optional_boolean_field(env, class, object, cstr!("headless"), self.headless);
+        set_optional_string_field(env, class, object, cstr!("profile"), &self.profile);
+        set_optional_string_field(env, class, object, cstr!("configDirectory"), &self.base_directory);
+        set_optional_string_field(env, class, object, cstr!("dataDirectory"), &self.data_directory);
+        set_optional_string_field(env, class, object, cstr!("stateDirectory"), &self.state_directory);
+        set_optional_string_field(env, class, object, cstr!("cacheDirectory"), &self.cache_directory);
+        set_optional_string_field(env, class, object, cstr!("displayServer"), &self.display_server);
+        if let Some(deep_link) = &self.deep_link {
+            set_optional_map_field(env, class, object, cstr!("deepLink"), &deep_link.fields());
+        }
+
+        Ok(object)
     }
 }
@@ -0,0 +1,44 @@
+use std::{
+    error::Error,
+    fs::File,
+    io::{self, Read},
+    path::Path,
+    time::Duration,
+};
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(30);
+const READ_TIMEOUT: Duration = Duration::from_secs(60);
+const MAX_ARCHIVE_SIZE: u64 = 1024 * 1024 * 1024;
+
+fn is_remote(source: &str) -> bool {
+    source.starts_with("http://") || source.starts_with("https://")
+}
+
+pub fn fetch_archive(source: &str, dest: &Path) -> Result<(), Box<dyn Error>> {
+    let partial = dest.with_extension("part");
+
+    if is_remote(source) {
+        // Stalled mirrors fail instead of blocking staging forever.
+        let agent = ureq::AgentBuilder::new()
+            .timeout_connect(CONNECT_TIMEOUT)
+            .timeout_read(READ_TIMEOUT)
+            .build();
+        let response = agent.get(source).call()?;
+
+        let copied = io::copy(
+            &mut response.into_reader().take(MAX_ARCHIVE_SIZE + 1),
+            &mut File::create(&partial)?,
+        )?;
+        if copied > MAX_ARCHIVE_SIZE {
+            let _ = std::fs::remove_file(&partial);
+
+            return Err(format!("Update archive exceeds {} bytes", MAX_ARCHIVE_SIZE).into());
+        }
+    } else {
+        std::fs::copy(source, &partial)?;
+    }
+
+    std::fs::rename(&partial, dest)?;
+
+    Ok(())
+}
@@ -0,0 +1,378 @@
+use std::{
+    error::Error,
+    ffi::OsString,
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    archive::extract_archive,
+    integrity::{verify_complete_bundle, BUNDLE_HASHES_NAME},
+    layout::{is_valid_version_name, set_current_version, InstallLayout, VERSIONS_DIR_NAME},
+    metadata::{resolve_app_metadata, Metadata, DEFAULT_APP_VERSION},
+    signature::verify_signatures,
+    update::{download::fetch_archive, pending::mark_pending},
+    utils::fs::{copy_path, remove_path},
+    APP_JAR_NAME,
+};
+
+mod download;
+pub mod pending;
+
+const UPDATES_DIR_NAME: &str = "updates";
+const DOWNLOAD_NAME: &str = "bundle.download";
+const STAGING_DIR_NAME: &str = "staging";
+const STAGED_DIR_NAME: &str = "staged";
+const PREVIOUS_DIR_NAME: &str = "previous";
+const READY_MARKER_NAME: &str = "ready";
+const APPLYING_MARKER_NAME: &str = "applying";
+
+pub enum SwapResult {
+    Unchanged,
+    Swapped,
+    StarterReplaced(PathBuf),
+}
+
+fn verify_staging(staging: &Path) -> Result<Option<Metadata>, Box<dyn Error>> {
+    let entries = std::fs::read_dir(staging)?.filter_map(|e| e.ok()).count();
+    if entries == 0 {
+        return Err("Update archive is empty".into());
+    }
+
+    // Archives may leave out the jar, hash list is required.
+    let jar = staging.join(APP_JAR_NAME);
+    let hashes_file = staging.join(BUNDLE_HASHES_NAME);
+    if jar.exists() {
+        verify_signatures(&[&jar, &hashes_file])?;
+    } else {
+        verify_signatures(&[&hashes_file])?;
+    }
+    verify_complete_bundle(staging)?;
+
+    if jar.exists() {
+        Ok(Some(resolve_app_metadata(&jar)?))
+    } else {
+        Ok(None)
+    }
+}
+
+// Moves verified files into `versions/<version>`, files absent in archive are copied from current version.
+fn install_version(layout: &InstallLayout, staging: &Path, metadata: Option<Metadata>) -> Result<String, Box<dyn Error>> {
+    let version = metadata
+        .map(|m| m.version)
+        .filter(|v| is_valid_version_name(v) && v != DEFAULT_APP_VERSION)
+        .ok_or("Update archive does not contain a versioned application jar")?;
+
+    if layout.version.as_deref() == Some(version.as_str()) {
+        return Err(format!("Version {} is already in use", version).into());
+    }
+
+    for entry in std::fs::read_dir(&layout.root)? {
+        let name = entry?.file_name();
+        if !staging.join(&name).exists() {
+            copy_path(&layout.root.join(&name), &staging.join(&name))?;
+        }
+    }
+
+    let target = layout.app_dir.join(VERSIONS_DIR_NAME).join(&version);
+
+    remove_path(&target)?;
+    std::fs::rename(staging, &target)?;
+
+    Ok(version)
+}
+
+// Downloads, verifies and extracts a bundle archive, it will be applied on next launch.
+pub fn stage_update(layout: &InstallLayout, source: &str) -> Result<(), Box<dyn Error>> {
+    let updates_dir = layout.app_dir.join(UPDATES_DIR_NAME);
+    let download = updates_dir.join(DOWNLOAD_NAME);
+    let staging = updates_dir.join(STAGING_DIR_NAME);
+    let staged = updates_dir.join(STAGED_DIR_NAME);
+    let ready = updates_dir.join(READY_MARKER_NAME);
+
+    if updates_dir.join(APPLYING_MARKER_NAME).exists() {
+        return Err("Previous update is still being applied".into());
+    }
+
+    std::fs::create_dir_all(&updates_dir)?;
+    remove_path(&ready)?;
+    remove_path(&staging)?;
+    remove_path(&staged)?;
+
+    fetch_archive(source, &download).map_err(|e| format!("Download {}: {}", source, e))?;
+
+    let result = extract_archive(&download, &staging).and_then(|_| verify_staging(&staging));
+
+    let _ = std::fs::remove_file(&download);
+
+    let result = result.and_then(|metadata| {
+        if layout.version.is_some() {
+            install_version(layout, &staging, metadata).map(Some)
+        } else {
+            std::fs::rename(&staging, &staged).map(|_| None).map_err(|e| e.into())
+        }
+    });
+
+    let version = match result {
+        Ok(version) => version,
+        Err(err) => {
+            let _ = remove_path(&staging);
+
+            return Err(err);
+        }
+    };
+
+    let mut marker = format!("source={}\n", source);
+    if let Some(version) = version {
+        marker.push_str(&format!("version={}\n", version));
+    }
+
+    std::fs::write(&ready, marker)?;
+
+    Ok(())
+}
+
+pub fn has_staged_update(app_dir: &Path) -> bool {
+    app_dir.join(UPDATES_DIR_NAME).join(READY_MARKER_NAME).exists()
+}
+
+fn staged_version(ready: &Path) -> Option<String> {
+    std::fs::read_to_string(ready)
+        .ok()?
+        .lines()
+        .find_map(|line| line.strip_prefix("version="))
+        .map(|v| v.to_owned())
+        .filter(|v| is_valid_version_name(v))
+}
+
+// Moves staged files into app directory, current ones are kept in `previous`.
+//
+// For versioned layout only the starter is moved, and the `current` pointer is
+// switched at last. Every step is a rename in the same directory tree, so an
+// interrupted swap can be resumed on next launch.
+pub fn apply_staged_update(layout: &InstallLayout, starter: &Path) -> Result<SwapResult, Box<dyn Error>> {
+    let app_dir = layout.app_dir.as_path();
+    let updates_dir = app_dir.join(UPDATES_DIR_NAME);
+    let previous = updates_dir.join(PREVIOUS_DIR_NAME);
+    let ready = updates_dir.join(READY_MARKER_NAME);
+    let applying = updates_dir.join(APPLYING_MARKER_NAME);
+
+    if !ready.exists() {
+        return Ok(SwapResult::Unchanged);
+    }
+
+    let version = staged_version(&ready);
+    let source = match &version {
+        Some(version) => app_dir.join(VERSIONS_DIR_NAME).join(version),
+        None => updates_dir.join(STAGED_DIR_NAME),
+    };
+    if !source.is_dir() {
+        return Ok(SwapResult::Unchanged);
+    }
+
+    let starter_name = starter.file_name().ok_or("Invalid starter path")?;
+    let swapped_entries = || -> std::io::Result<Vec<OsString>> {
+        std::fs::read_dir(&source)?
+            .map(|entry| entry.map(|e| e.file_name()))
+            .filter(|name| match name {
+                Ok(name) if version.is_some() => name == starter_name,
+                Ok(name) => name != UPDATES_DIR_NAME,
+                Err(_) => true,
+            })
+            .collect()
+    };
+
+    if !applying.exists() {
+        remove_path(&previous)?;
+        std::fs::create_dir_all(&previous)?;
+        mark_pending(&updates_dir, swapped_entries()?, layout.version.clone())?;
+        std::fs::write(&applying, "")?;
+    }
+
+    let mut starter_replaced = false;
+
+    for name in swapped_entries()? {
+        let current = app_dir.join(&name);
+
+        _ = std::io::stderr().write_fmt(format_args!("[Starter] file={} | Applying update\n", name.to_string_lossy()));
+
+        if current.exists() {
+            std::fs::rename(&current, previous.join(&name))?;
+        }
+
+        std::fs::rename(source.join(&name), &current)?;
+
+        starter_replaced |= name == starter_name;
+    }
+
+    if let Some(version) = &version {
+        _ = std::io::stderr().write_fmt(format_args!("[Starter] version={} | Applying update\n", version));
+
+        set_current_version(app_dir, version)?;
+    }
+
+    remove_path(&updates_dir.join(STAGED_DIR_NAME))?;
+    remove_path(&ready)?;
+    remove_path(&applying)?;
+
+    if starter_replaced {
+        Ok(SwapResult::StarterReplaced(app_dir.join(starter_name)))
+    } else {
+        Ok(SwapResult::Swapped)
+    }
+}
+
+pub fn restart_starter(starter: &Path) -> Result<(), Box<dyn Error>> {
+    let mut command = std::process::Command::new(starter);
+    command.args(std::env::args_os().skip(1));
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::CommandExt;
+
+        Err(command.exec().into())
+    }
+
+    #[cfg(windows)]
+    {
+        command.spawn()?;
+
+        std::process::exit(0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::{Cursor, Read, Write},
+        net::TcpListener,
+        path::{Path, PathBuf},
+        thread::JoinHandle,
+    };
+
+    use sha2::{Digest, Sha256};
+
+    use super::{apply_staged_update, stage_update, SwapResult, PREVIOUS_DIR_NAME, STAGED_DIR_NAME, UPDATES_DIR_NAME};
+    use crate::{integrity::BUNDLE_HASHES_NAME, layout::resolve_layout, utils::strings::encode_hex};
+
+    const STARTER: &str = "clash-multiplatform";
+
+    fn temp_app_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("starter-test-{}-{}", name, std::process::id()));
+
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join("jre")).unwrap();
+        std::fs::write(dir.join(STARTER), "old starter").unwrap();
+        std::fs::write(dir.join("jre/release"), "old runtime").unwrap();
+
+        dir
+    }
+
+    // Hash list covers `hashed` only, it is left out if that is `None`.
+    fn build_archive(files: &[(&str, &str)], hashed: Option<&[&str]>) -> Vec<u8> {
+        let manifest = hashed.map(|hashed| {
+            files
+                .iter()
+                .filter(|(name, _)| hashed.contains(name))
+                .map(|(name, content)| format!("{}  {}\n", encode_hex(&Sha256::digest(content)), name))
+                .collect::<String>()
+        });
+
+        let mut zip = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        let options = zip::write::FileOptions::default();
+        for (name, content) in files
+            .iter()
+            .copied()
+            .chain(manifest.as_deref().map(|m| (BUNDLE_HASHES_NAME, m)))
+        {
+            zip.start_file(name, options).unwrap();
+            zip.write_all(content.as_bytes()).unwrap();
+        }
+
+        zip.finish().unwrap().into_inner()
+    }
+
+    // Answers a single GET with `body`.
+    fn serve_once(body: Vec<u8>) -> (String, JoinHandle<()>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/bundle.zip", listener.local_addr().unwrap());
+
+        let server = std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+
+            let mut request = Vec::new();
+            let mut buf = [0u8; 1024];
+            while !request.ends_with(b"\r\n\r\n") {
+                let length = stream.read(&mut buf).unwrap();
+                assert_ne!(length, 0);
+                request.extend_from_slice(&buf[..length]);
+            }
+            assert!(request.starts_with(b"GET /bundle.zip "));
+
+            let header = format!(
+                "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                body.len()
+            );
+            stream.write_all(header.as_bytes()).unwrap();
+            stream.write_all(&body).unwrap();
+        });
+
+        (url, server)
+    }
+
+    fn read(path: &Path) -> String {
+        std::fs::read_to_string(path).unwrap()
+    }
+
+    #[test]
+    fn stages_and_swaps_downloaded_bundle() {
+        let app_dir = temp_app_dir("swap");
+        let files = [(STARTER, "new starter"), ("jre/release", "new runtime")];
+        let (url, server) = serve_once(build_archive(&files, Some(&[STARTER, "jre/release"])));
+
+        let layout = resolve_layout(&app_dir, None).unwrap();
+        stage_update(&layout, &url).unwrap();
+        server.join().unwrap();
+
+        let updates_dir = app_dir.join(UPDATES_DIR_NAME);
+        assert_eq!(read(&updates_dir.join(STAGED_DIR_NAME).join(STARTER)), "new starter");
+        assert_eq!(read(&app_dir.join(STARTER)), "old starter");
+
+        let result = apply_staged_update(&layout, &app_dir.join(STARTER)).unwrap();
+        assert!(matches!(result, SwapResult::StarterReplaced(path) if path == app_dir.join(STARTER)));
+
+        assert_eq!(read(&app_dir.join(STARTER)), "new starter");
+        assert_eq!(read(&app_dir.join("jre/release")), "new runtime");
+        assert!(read(&app_dir.join(BUNDLE_HASHES_NAME)).contains("jre/release"));
+        assert_eq!(read(&updates_dir.join(PREVIOUS_DIR_NAME).join(STARTER)), "old starter");
+        assert_eq!(read(&updates_dir.join(PREVIOUS_DIR_NAME).join("jre/release")), "old runtime");
+        assert!(!updates_dir.join(STAGED_DIR_NAME).exists());
+
+        let result = apply_staged_update(&layout, &app_dir.join(STARTER)).unwrap();
+        assert!(matches!(result, SwapResult::Unchanged));
+
+        std::fs::remove_dir_all(&app_dir).unwrap();
+    }
+
+    #[test]
+    fn refuses_files_without_hash() {
+        let files = [(STARTER, "new starter"), ("jre/release", "new runtime")];
+
+        for (name, hashed, error) in [
+            ("unlisted", Some(&["jre/release"][..]), "unlisted: clash-multiplatform"),
+            ("unhashed", None, "does not contain bundle.sha256"),
+        ] {
+            let app_dir = temp_app_dir(name);
+            let (url, server) = serve_once(build_archive(&files, hashed));
+
+            let err = stage_update(&resolve_layout(&app_dir, None).unwrap(), &url).err().unwrap();
+            server.join().unwrap();
+
+            assert!(err.to_string().contains(error), "{}", err);
+            assert!(!app_dir.join(UPDATES_DIR_NAME).join(STAGED_DIR_NAME).exists());
+            assert_eq!(read(&app_dir.join(STARTER)), "old starter");
+
+            std::fs::remove_dir_all(&app_dir).unwrap();
+        }
+    }
+}
@@ -0,0 +1,189 @@
+use std::{
+    error::Error,
+    ffi::OsString,
+    fs::OpenOptions,
+    io::Write,
+    path::Path,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::{
+    layout::{is_valid_version_name, set_current_version},
+    update::{SwapResult, PREVIOUS_DIR_NAME, UPDATES_DIR_NAME},
+    utils::fs::remove_path,
+};
+
+const MAX_PENDING_LAUNCHES: u32 = 3;
+
+const PENDING_NAME: &str = "pending";
+const CONFIRMED_MARKER_NAME: &str = "confirmed";
+const FAILED_DIR_NAME: &str = "failed";
+const ROLLBACK_LOG_NAME: &str = "rollback.log";
+
+// Update that has not yet been confirmed by a successful launch.
+//
+// File format, one "key=value" per line:
+//   attempts=<launches since update applied>
+//   entry=<swapped file name>   (repeated)
+//   previous_version=<version to restore, versioned layout only>
+//   error=<last launch error>
+#[derive(Default)]
+struct PendingUpdate {
+    attempts: u32,
+    entries: Vec<OsString>,
+    previous_version: Option<String>,
+    error: Option<String>,
+}
+
+impl PendingUpdate {
+    fn load(updates_dir: &Path) -> Option<PendingUpdate> {
+        let content = std::fs::read_to_string(updates_dir.join(PENDING_NAME)).ok()?;
+
+        let mut pending = PendingUpdate::default();
+        for (key, value) in content.lines().filter_map(|line| line.split_once('=')) {
+            match key {
+                "attempts" => pending.attempts = value.parse().unwrap_or(MAX_PENDING_LAUNCHES),
+                "entry" => pending.entries.push(value.into()),
+                "previous_version" if is_valid_version_name(value) => pending.previous_version = Some(value.to_owned()),
+                "error" => pending.error = Some(value.to_owned()),
+                _ => {}
+            }
+        }
+
+        Some(pending)
+    }
+
+    fn save(&self, updates_dir: &Path) -> std::io::Result<()> {
+        let mut content = format!("attempts={}\n", self.attempts);
+
+        for entry in &self.entries {
+            content.push_str(&format!("entry={}\n", entry.to_string_lossy()));
+        }
+
+        if let Some(version) = &self.previous_version {
+            content.push_str(&format!("previous_version={}\n", version));
+        }
+
+        if let Some(error) = &self.error {
+            content.push_str(&format!("error={}\n", error.replace('\n', " ")));
+        }
+
+        std::fs::write(updates_dir.join(PENDING_NAME), content)
+    }
+}
+
+pub fn mark_pending(updates_dir: &Path, entries: Vec<OsString>, previous_version: Option<String>) -> std::io::Result<()> {
+    let _ = std::fs::remove_file(updates_dir.join(CONFIRMED_MARKER_NAME));
+
+    PendingUpdate {
+        attempts: 0,
+        entries,
+        previous_version,
+        error: None,
+    }
+    .save(updates_dir)
+}
+
+fn append_rollback_log(updates_dir: &Path, pending: &PendingUpdate) -> std::io::Result<()> {
+    let timestamp = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+    let reason = pending
+        .error
+        .clone()
+        .unwrap_or_else(|| "application did not confirm startup".to_owned());
+
+    let mut log = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(updates_dir.join(ROLLBACK_LOG_NAME))?;
+
+    log.write_fmt(format_args!(
+        "{} attempts={} reason={}\n",
+        timestamp, pending.attempts, reason
+    ))
+}
+
+// Restores files of previous version, files of failed version are kept in `failed`.
+fn rollback(app_dir: &Path, starter: &Path, pending: &PendingUpdate) -> Result<SwapResult, Box<dyn Error>> {
+    let updates_dir = app_dir.join(UPDATES_DIR_NAME);
+    let previous = updates_dir.join(PREVIOUS_DIR_NAME);
+    let failed = updates_dir.join(FAILED_DIR_NAME);
+
+    remove_path(&failed)?;
+    std::fs::create_dir_all(&failed)?;
+
+    let starter_name = starter.file_name().ok_or("Invalid starter path")?;
+    let mut starter_replaced = false;
+
+    for name in &pending.entries {
+        let current = app_dir.join(name);
+
+        if current.exists() {
+            std::fs::rename(&current, failed.join(name))?;
+        }
+
+        if previous.join(name).exists() {
+            std::fs::rename(previous.join(name), &current)?;
+        }
+
+        starter_replaced |= name == starter_name;
+    }
+
+    if let Some(version) = &pending.previous_version {
+        set_current_version(app_dir, version)?;
+    }
+
+    append_rollback_log(&updates_dir, pending)?;
+    remove_path(&updates_dir.join(PENDING_NAME))?;
+
+    if starter_replaced {
+        Ok(SwapResult::StarterReplaced(app_dir.join(starter_name)))
+    } else {
+        Ok(SwapResult::Swapped)
+    }
+}
+
+// Counts launches of a pending update and rolls back once it keeps failing.
+pub fn check_pending_update(app_dir: &Path, starter: &Path) -> Result<SwapResult, Box<dyn Error>> {
+    let updates_dir = app_dir.join(UPDATES_DIR_NAME);
+
+    if updates_dir.join(CONFIRMED_MARKER_NAME).exists() {
+        confirm_update(app_dir)?;
+    }
+
+    let mut pending = match PendingUpdate::load(&updates_dir) {
+        Some(pending) => pending,
+        None => return Ok(SwapResult::Unchanged),
+    };
+
+    if pending.attempts >= MAX_PENDING_LAUNCHES {
+        _ = std::io::stderr().write_fmt(format_args!(
+            "[Starter] attempts={} | Rolling back update\n",
+            pending.attempts
+        ));
+
+        return rollback(app_dir, starter, &pending);
+    }
+
+    pending.attempts += 1;
+    pending.save(&updates_dir)?;
+
+    Ok(SwapResult::Unchanged)
+}
+
+pub fn record_launch_failure(app_dir: &Path, error: &str) -> std::io::Result<()> {
+    let updates_dir = app_dir.join(UPDATES_DIR_NAME);
+
+    if let Some(mut pending) = PendingUpdate::load(&updates_dir) {
+        pending.error = Some(error.to_owned());
+        pending.save(&updates_dir)?;
+    }
+
+    Ok(())
+}
+
+pub fn confirm_update(app_dir: &Path) -> std::io::Result<()> {
+    let updates_dir = app_dir.join(UPDATES_DIR_NAME);
+
+    remove_path(&updates_dir.join(PENDING_NAME))?;
+    remove_path(&updates_dir.join(CONFIRMED_MARKER_NAME))
+}
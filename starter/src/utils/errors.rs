@@ -28,9 +28,9 @@ pub trait ErrorExt {
 
 impl ErrorExt for Box<dyn Error> {
     fn with_message(self, msg: &str) -> MessagedError {
-        return MessagedError {
+        MessagedError {
             message: msg.to_owned(),
             error: self,
-        };
+        }
     }
 }
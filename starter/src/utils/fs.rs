@@ -0,0 +1,34 @@
+use std::path::Path;
+
+pub fn remove_path(path: &Path) -> std::io::Result<()> {
+    if path.is_dir() {
+        std::fs::remove_dir_all(path)
+    } else if path.exists() {
+        std::fs::remove_file(path)
+    } else {
+        Ok(())
+    }
+}
+
+pub fn copy_path(src: &Path, dst: &Path) -> std::io::Result<()> {
+    let metadata = std::fs::symlink_metadata(src)?;
+
+    if metadata.is_dir() {
+        std::fs::create_dir_all(dst)?;
+
+        for entry in std::fs::read_dir(src)? {
+            let entry = entry?;
+
+            copy_path(&entry.path(), &dst.join(entry.file_name()))?;
+        }
+
+        return Ok(());
+    }
+
+    #[cfg(unix)]
+    if metadata.is_symlink() {
+        return std::os::unix::fs::symlink(std::fs::read_link(src)?, dst);
+    }
+
+    std::fs::copy(src, dst).map(|_| ())
+}
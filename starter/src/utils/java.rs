@@ -1,6 +1,7 @@
 use std::{ptr::null_mut, slice};
 
-use jni_sys::{jint, jstring, JNIEnv};
+use cstr::cstr;
+use jni_sys::{jint, jobjectArray, jsize, jstring, JNIEnv};
 
 macro_rules! jcall {
     ($ctx:expr, $func_name:ident) => {
@@ -38,3 +39,14 @@ impl JStringExt for String {
         jcall!(env, NewString, chars.as_ptr(), chars.len() as jint)
     }
 }
+
+pub fn new_java_string_array(env: *mut JNIEnv, values: &[String]) -> jobjectArray {
+    let c_string = jcall!(env, FindClass, cstr!("java/lang/String").as_ptr());
+    let array = jcall!(env, NewObjectArray, values.len() as jsize, c_string, null_mut());
+
+    for (idx, value) in values.iter().enumerate() {
+        jcall!(env, SetObjectArrayElement, array, idx as jsize, value.to_java_string(env));
+    }
+
+    array
+}
@@ -1,3 +1,5 @@
 pub mod errors;
+pub mod fs;
 pub mod java;
 pub mod strings;
+pub mod version;
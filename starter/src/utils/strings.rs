@@ -2,6 +2,9 @@ use std::path::Path;
 
 pub trait PathExt {
     fn to_string_without_extend_length_mark(&self) -> String;
+
+    #[cfg(windows)]
+    fn strip_extend_length_mark(&self) -> &Path;
 }
 
 impl PathExt for Path {
@@ -14,4 +17,55 @@ impl PathExt for Path {
         #[cfg(not(windows))]
         return ret.to_string();
     }
+
+    #[cfg(windows)]
+    fn strip_extend_length_mark(&self) -> &Path {
+        self.to_str()
+            .and_then(|s| s.strip_prefix("\\\\?\\"))
+            .map(Path::new)
+            .unwrap_or(self)
+    }
+}
+
+pub fn encode_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+pub fn decode_hex(text: &str) -> Option<Vec<u8>> {
+    let text = text.trim();
+
+    (0..text.len())
+        .step_by(2)
+        .map(|idx| u8::from_str_radix(text.get(idx..idx + 2)?, 16).ok())
+        .collect()
+}
+
+// `+` means space in query strings only, paths keep it as is.
+pub fn decode_percent(text: &str, plus_as_space: bool) -> Option<String> {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+
+    let mut idx = 0;
+    while idx < bytes.len() {
+        match bytes[idx] {
+            b'%' => {
+                let hex = bytes
+                    .get(idx + 1..idx + 3)
+                    .filter(|hex| hex.iter().all(u8::is_ascii_hexdigit))?;
+
+                decoded.push(u8::from_str_radix(std::str::from_utf8(hex).ok()?, 16).ok()?);
+                idx += 3;
+            }
+            b'+' if plus_as_space => {
+                decoded.push(b' ');
+                idx += 1;
+            }
+            b => {
+                decoded.push(b);
+                idx += 1;
+            }
+        }
+    }
+
+    String::from_utf8(decoded).ok()
 }
@@ -0,0 +1,19 @@
+use std::cmp::Ordering;
+
+fn parse_components(version: &str) -> Vec<u64> {
+    version.split(['.', '-', '+']).map_while(|s| s.parse::<u64>().ok()).collect()
+}
+
+pub fn compare_versions(a: &str, b: &str) -> Ordering {
+    let a = parse_components(a);
+    let b = parse_components(b);
+
+    for idx in 0..a.len().max(b.len()) {
+        let ordering = a.get(idx).unwrap_or(&0).cmp(b.get(idx).unwrap_or(&0));
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+
+    Ordering::Equal
+}
@@ -0,0 +1,135 @@
+use std::{
+    error::Error,
+    ffi::{CString, OsStr},
+    path::{Component, Path, PathBuf},
+};
+
+#[cfg(windows)]
+use crate::utils::strings::PathExt;
+
+#[cfg(windows)]
+const PATH_LIST_SEPARATOR: u8 = b';';
+
+#[cfg(target_os = "linux")]
+const PATH_LIST_SEPARATOR: u8 = b':';
+
+pub enum VMOption {
+    Flag(String),
+    Property(String, String),
+    PathList(String, Vec<PathBuf>),
+}
+
+#[derive(Default)]
+pub struct VMOptions {
+    options: Vec<VMOption>,
+}
+
+fn encode_platform_string(s: &OsStr) -> Option<Vec<u8>> {
+    #[cfg(windows)]
+    return crate::win32::strings::to_platform_encoding(s);
+
+    #[cfg(target_os = "linux")]
+    return crate::linux::strings::to_platform_encoding(s);
+}
+
+fn encode_text(text: &str) -> Result<Vec<u8>, Box<dyn Error>> {
+    encode_platform_string(OsStr::new(text))
+        .ok_or_else(|| format!("'{}' can not be represented in platform encoding", text).into())
+}
+
+// Shared leading components are dropped, the rest of current dir is climbed out of with `..`.
+fn relative_to_current_dir(path: &Path) -> Option<PathBuf> {
+    let current_dir = std::env::current_dir().ok()?;
+    if !path.is_absolute() {
+        return None;
+    }
+
+    let mut path_components = path.components().peekable();
+    let mut current_components = current_dir.components().peekable();
+    while let (Some(a), Some(b)) = (path_components.peek(), current_components.peek()) {
+        if a != b {
+            break;
+        }
+
+        path_components.next();
+        current_components.next();
+    }
+
+    let mut relative: PathBuf = current_components.map(|_| Component::ParentDir).collect();
+    relative.extend(path_components);
+    if relative.as_os_str().is_empty() {
+        relative.push(Component::CurDir);
+    }
+
+    Some(relative)
+}
+
+fn encode_path(path: &Path) -> Result<Vec<u8>, Box<dyn Error>> {
+    #[cfg(windows)]
+    let path = path.strip_extend_length_mark();
+
+    let encoded = encode_platform_string(path.as_os_str());
+
+    #[cfg(windows)]
+    let encoded =
+        encoded.or_else(|| crate::win32::dirs::short_path_name(path).and_then(|p| encode_platform_string(p.as_os_str())));
+
+    // Relative paths skip the unrepresentable part the path shares with launch directory.
+    let encoded = encoded.or_else(|| relative_to_current_dir(path).and_then(|p| encode_platform_string(p.as_os_str())));
+
+    match encoded {
+        Some(encoded) if encoded.contains(&PATH_LIST_SEPARATOR) => {
+            Err(format!("Path '{}' contains path list separator", path.display()).into())
+        }
+        Some(encoded) => Ok(encoded),
+        None => Err(format!("Path '{}' can not be represented in platform encoding", path.display()).into()),
+    }
+}
+
+impl VMOption {
+    fn encode(&self) -> Result<CString, Box<dyn Error>> {
+        let encoded = match self {
+            VMOption::Flag(flag) => encode_text(flag)?,
+            VMOption::Property(key, value) => encode_text(&format!("-D{}={}", key, value))?,
+            VMOption::PathList(prefix, paths) => {
+                let mut encoded = encode_text(prefix)?;
+
+                for (idx, path) in paths.iter().enumerate() {
+                    if idx > 0 {
+                        encoded.push(PATH_LIST_SEPARATOR);
+                    }
+
+                    encoded.extend(encode_path(path)?);
+                }
+
+                encoded
+            }
+        };
+
+        CString::new(encoded).map_err(|_| "JVM option contains NUL character".into())
+    }
+}
+
+impl VMOptions {
+    pub fn flag(&mut self, flag: &str) -> &mut Self {
+        self.options.push(VMOption::Flag(flag.to_owned()));
+
+        self
+    }
+
+    pub fn property(&mut self, key: &str, value: &str) -> &mut Self {
+        self.options.push(VMOption::Property(key.to_owned(), value.to_owned()));
+
+        self
+    }
+
+    pub fn path_list(&mut self, prefix: &str, paths: &[PathBuf]) -> &mut Self {
+        self.options.push(VMOption::PathList(prefix.to_owned(), paths.to_vec()));
+
+        self
+    }
+
+    pub fn encode(&self) -> Result<Vec<CString>, Box<dyn Error>> {
+        self.options.iter().map(|o| o.encode()).collect()
+    }
+}
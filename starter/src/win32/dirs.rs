@@ -1,7 +1,15 @@
-use std::{error::Error, ffi::OsString, os::windows::ffi::OsStringExt, path::PathBuf, ptr::null_mut, slice};
+use std::{
+    error::Error,
+    ffi::OsString,
+    os::windows::ffi::{OsStrExt, OsStringExt},
+    path::{Path, PathBuf},
+    ptr::null_mut,
+    slice,
+};
 
 use windows_sys::Win32::{
     Foundation::S_OK,
+    Storage::FileSystem::GetShortPathNameW,
     UI::Shell::{FOLDERID_LocalAppData, SHGetKnownFolderPath},
 };
 
@@ -22,3 +30,20 @@ pub fn current_user_local_directory() -> Result<PathBuf, Box<dyn Error>> {
 
     Ok(PathBuf::from(ret))
 }
+
+pub fn short_path_name(path: &Path) -> Option<PathBuf> {
+    let long_path = path.as_os_str().encode_wide().chain(Some(0)).collect::<Vec<u16>>();
+
+    let length = unsafe { GetShortPathNameW(long_path.as_ptr(), null_mut(), 0) };
+    if length == 0 {
+        return None;
+    }
+
+    let mut short_path = vec![0u16; length as usize];
+    let length = unsafe { GetShortPathNameW(long_path.as_ptr(), short_path.as_mut_ptr(), short_path.len() as u32) };
+    if length == 0 || length as usize >= short_path.len() {
+        return None;
+    }
+
+    Some(PathBuf::from(OsString::from_wide(&short_path[..length as usize])))
+}
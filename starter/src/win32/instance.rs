@@ -0,0 +1,122 @@
+use std::{
+    fs::File,
+    io,
+    os::windows::{ffi::OsStrExt, io::FromRawHandle},
+    ptr::{null, null_mut},
+    thread,
+    time::Duration,
+};
+
+use windows_sys::Win32::{
+    Foundation::{
+        CloseHandle, GetLastError, ERROR_ALREADY_EXISTS, ERROR_FILE_NOT_FOUND, ERROR_PIPE_CONNECTED, INVALID_HANDLE_VALUE,
+    },
+    Storage::FileSystem::PIPE_ACCESS_INBOUND,
+    System::{
+        Pipes::{
+            ConnectNamedPipe, CreateNamedPipeW, PIPE_REJECT_REMOTE_CLIENTS, PIPE_TYPE_BYTE, PIPE_UNLIMITED_INSTANCES, PIPE_WAIT,
+        },
+        Threading::{CreateMutexW, OpenMutexW, SYNCHRONIZATION_SYNCHRONIZE},
+    },
+};
+
+const CONNECT_ATTEMPTS: u32 = 20;
+const CONNECT_INTERVAL: Duration = Duration::from_millis(100);
+const PIPE_BUFFER_SIZE: u32 = 4096;
+
+pub enum InstanceChannel {
+    Primary(PipeListener),
+    Secondary(File),
+}
+
+pub struct PipeListener {
+    name: Vec<u16>,
+}
+
+fn to_wide(s: &str) -> Vec<u16> {
+    std::ffi::OsStr::new(s).encode_wide().chain(Some(0)).collect()
+}
+
+fn mutex_name(id: &str) -> Vec<u16> {
+    to_wide(&format!("Local\\clash-multiplatform-{}", id))
+}
+
+fn pipe_name(id: &str) -> String {
+    format!("\\\\.\\pipe\\clash-multiplatform-{}", id)
+}
+
+impl PipeListener {
+    // Every client is served by a new pipe instance, clients arriving in between retry.
+    pub fn accept(&self) -> io::Result<File> {
+        let pipe = unsafe {
+            CreateNamedPipeW(
+                self.name.as_ptr(),
+                PIPE_ACCESS_INBOUND,
+                PIPE_TYPE_BYTE | PIPE_WAIT | PIPE_REJECT_REMOTE_CLIENTS,
+                PIPE_UNLIMITED_INSTANCES,
+                0,
+                PIPE_BUFFER_SIZE,
+                0,
+                null(),
+            )
+        };
+        if pipe == INVALID_HANDLE_VALUE {
+            return Err(io::Error::last_os_error());
+        }
+
+        let file = unsafe { File::from_raw_handle(pipe as _) };
+
+        if unsafe { ConnectNamedPipe(pipe, null_mut()) } == 0 && unsafe { GetLastError() } != ERROR_PIPE_CONNECTED {
+            return Err(io::Error::last_os_error());
+        }
+
+        Ok(file)
+    }
+}
+
+pub fn is_instance_locked(id: &str) -> io::Result<bool> {
+    let mutex = unsafe { OpenMutexW(SYNCHRONIZATION_SYNCHRONIZE, 0, mutex_name(id).as_ptr()) };
+    if mutex != 0 {
+        unsafe { CloseHandle(mutex) };
+
+        return Ok(true);
+    }
+
+    let err = io::Error::last_os_error();
+    if err.raw_os_error() == Some(ERROR_FILE_NOT_FOUND as i32) {
+        Ok(false)
+    } else {
+        Err(err)
+    }
+}
+
+// Named mutex lives as long as its owner, so a crashed instance never blocks the next one.
+pub fn open_instance_channel(id: &str) -> io::Result<InstanceChannel> {
+    let mutex = unsafe { CreateMutexW(null(), 0, mutex_name(id).as_ptr()) };
+    if mutex == 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    if unsafe { GetLastError() } != ERROR_ALREADY_EXISTS {
+        // Held until process exits.
+        return Ok(InstanceChannel::Primary(PipeListener {
+            name: to_wide(&pipe_name(id)),
+        }));
+    }
+
+    unsafe { CloseHandle(mutex) };
+
+    // Primary may still be creating its pipe.
+    let mut attempts = 0;
+    loop {
+        match File::options().write(true).open(pipe_name(id)) {
+            Ok(pipe) => return Ok(InstanceChannel::Secondary(pipe)),
+            Err(err) if attempts >= CONNECT_ATTEMPTS => return Err(err),
+            Err(_) => {
+                attempts += 1;
+
+                thread::sleep(CONNECT_INTERVAL);
+            }
+        }
+    }
+}
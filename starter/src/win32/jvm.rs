@@ -1,8 +1,7 @@
 use std::{
     error::Error,
-    io,
-    iter::once,
-    mem,
+    ffi::CString,
+    io, mem,
     os::raw::c_void,
     path::{Path, PathBuf},
     ptr::null_mut,
@@ -40,7 +39,7 @@ fn find_jvm_dll(app_root: &Path) -> Result<PathBuf, Box<dyn Error>> {
         if let Some(jvm_dll_path) = java_bin_path
             .parent()
             .and_then(|p| p.parent())
-            .and_then(|p| find_jvm_dll_from_jre_path(p))
+            .and_then(find_jvm_dll_from_jre_path)
         {
             return Ok(jvm_dll_path);
         }
@@ -55,11 +54,7 @@ fn find_jvm_dll(app_root: &Path) -> Result<PathBuf, Box<dyn Error>> {
     Err("JavaRuntime not found".into())
 }
 
-fn build_jvm_init_args(args: &[&str]) -> (Vec<Vec<u8>>, Vec<JavaVMOption>, JavaVMInitArgs) {
-    let args = args
-        .iter()
-        .map(|s| (*s).chars().map(|c| c as u8).chain(once(0)).collect::<Vec<_>>())
-        .collect::<Vec<_>>();
+fn build_jvm_init_args(args: &[CString]) -> (Vec<JavaVMOption>, JavaVMInitArgs) {
     let options = args
         .iter()
         .map(|a| JavaVMOption {
@@ -74,7 +69,7 @@ fn build_jvm_init_args(args: &[&str]) -> (Vec<Vec<u8>>, Vec<JavaVMOption>, JavaV
         ignoreUnrecognized: 0,
     };
 
-    (args, options, vm_args)
+    (options, vm_args)
 }
 
 pub struct JavaRuntime {
@@ -88,7 +83,10 @@ impl Drop for JavaRuntime {
     fn drop(&mut self) {
         unsafe {
             if let Some(destroy_func) = GetProcAddress(self.jvm_module, cstr!("DestroyJavaVM").as_ptr().cast()) {
-                let destroy_func = mem::transmute::<_, unsafe extern "system" fn(*mut JavaVM) -> jint>(destroy_func);
+                let destroy_func = mem::transmute::<
+                    unsafe extern "system" fn() -> isize,
+                    unsafe extern "system" fn(*mut JavaVM) -> jint,
+                >(destroy_func);
 
                 destroy_func(self.vm);
             }
@@ -96,7 +94,7 @@ impl Drop for JavaRuntime {
     }
 }
 
-pub fn load_jvm(app_root: &Path, args: &[&str]) -> Result<JavaRuntime, Box<dyn Error>> {
+pub fn load_jvm(app_root: &Path, args: &[CString]) -> Result<JavaRuntime, Box<dyn Error>> {
     let jvm_dll_path = find_jvm_dll(app_root)?.to_string_without_extend_length_mark();
 
     let jvm_module = unsafe {
@@ -113,13 +111,14 @@ pub fn load_jvm(app_root: &Path, args: &[&str]) -> Result<JavaRuntime, Box<dyn E
             None => {
                 return Err(format!("Invalid JavaRuntime: {}", jvm_dll_path).into());
             }
-            Some(func) => {
-                mem::transmute::<_, unsafe extern "system" fn(*mut *mut JavaVM, *mut *mut c_void, *mut c_void) -> jint>(func)
-            }
+            Some(func) => mem::transmute::<
+                unsafe extern "system" fn() -> isize,
+                unsafe extern "system" fn(*mut *mut JavaVM, *mut *mut c_void, *mut c_void) -> jint,
+            >(func),
         }
     };
 
-    let (args, options, mut vm_args) = build_jvm_init_args(args);
+    let (options, mut vm_args) = build_jvm_init_args(args);
 
     let mut vm: *mut JavaVM = null_mut();
     let mut env: *mut JNIEnv = null_mut();
@@ -134,7 +133,6 @@ pub fn load_jvm(app_root: &Path, args: &[&str]) -> Result<JavaRuntime, Box<dyn E
         }
     }
 
-    drop(args);
     drop(options);
 
     Ok(JavaRuntime { jvm_module, vm, env })
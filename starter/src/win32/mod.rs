@@ -1,6 +1,6 @@
-mod strings;
-
 pub mod dirs;
+pub mod instance;
 pub mod jvm;
 pub mod redirect;
+pub mod strings;
 pub mod ui;
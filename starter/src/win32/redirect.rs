@@ -1,8 +1,14 @@
 use std::{error::Error, io, os::windows::io::RawHandle};
 
 use windows_sys::Win32::{
-    Foundation::{SetHandleInformation, FALSE, HANDLE, HANDLE_FLAG_INHERIT, TRUE},
-    System::Console::{AllocConsole, GetConsoleWindow, SetStdHandle, STD_ERROR_HANDLE, STD_OUTPUT_HANDLE},
+    Foundation::{
+        SetHandleInformation, FALSE, GENERIC_READ, GENERIC_WRITE, HANDLE, HANDLE_FLAG_INHERIT, INVALID_HANDLE_VALUE, TRUE,
+    },
+    Storage::FileSystem::{CreateFileW, FILE_SHARE_WRITE, OPEN_EXISTING},
+    System::Console::{
+        AllocConsole, AttachConsole, GetConsoleWindow, GetStdHandle, SetStdHandle, ATTACH_PARENT_PROCESS, STD_ERROR_HANDLE,
+        STD_OUTPUT_HANDLE,
+    },
     UI::WindowsAndMessaging::{ShowWindow, SW_HIDE},
 };
 
@@ -37,3 +43,34 @@ pub fn set_standard_input_output(stdio: StandardInputOutput, fd: RawHandle) -> R
 
     Ok(())
 }
+
+// GUI subsystem starter has no console, command line usage writes to the one it was started from.
+// Handles redirected by the parent are kept.
+pub fn attach_parent_console() {
+    unsafe {
+        if AttachConsole(ATTACH_PARENT_PROCESS) == FALSE {
+            return;
+        }
+
+        let name = "CONOUT$\0".encode_utf16().collect::<Vec<_>>();
+        for id in [STD_OUTPUT_HANDLE, STD_ERROR_HANDLE] {
+            let handle = GetStdHandle(id);
+            if handle != 0 && handle != INVALID_HANDLE_VALUE {
+                continue;
+            }
+
+            let console = CreateFileW(
+                name.as_ptr(),
+                GENERIC_READ | GENERIC_WRITE,
+                FILE_SHARE_WRITE,
+                std::ptr::null(),
+                OPEN_EXISTING,
+                0,
+                0,
+            );
+            if console != INVALID_HANDLE_VALUE {
+                SetStdHandle(id, console);
+            }
+        }
+    }
+}
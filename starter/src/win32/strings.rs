@@ -1,4 +1,15 @@
-use std::{iter::once, ops::Deref};
+use std::{
+    ffi::OsStr,
+    iter::once,
+    ops::Deref,
+    os::windows::ffi::OsStrExt,
+    ptr::{null, null_mut},
+};
+
+use windows_sys::Win32::{
+    Foundation::FALSE,
+    Globalization::{GetACP, WideCharToMultiByte, CP_ACP, CP_UTF8, WC_NO_BEST_FIT_CHARS},
+};
 
 pub trait Win32Strings {
     fn to_win32_utf16(&self) -> Vec<u16>;
@@ -9,3 +20,51 @@ impl<T: Deref<Target = str>> Win32Strings for T {
         self.encode_utf16().chain(once(0)).collect::<Vec<u16>>()
     }
 }
+
+// JVM decodes option strings with the ANSI code page.
+pub fn to_platform_encoding(s: &OsStr) -> Option<Vec<u8>> {
+    let wide = s.encode_wide().collect::<Vec<u16>>();
+    if wide.is_empty() {
+        return Some(Vec::new());
+    }
+
+    if unsafe { GetACP() } == CP_UTF8 {
+        return s.to_str().map(|s| s.as_bytes().to_vec());
+    }
+
+    unsafe {
+        let mut used_default_char = FALSE;
+        let length = WideCharToMultiByte(
+            CP_ACP,
+            WC_NO_BEST_FIT_CHARS,
+            wide.as_ptr(),
+            wide.len() as i32,
+            null_mut(),
+            0,
+            null(),
+            &mut used_default_char,
+        );
+        if length <= 0 || used_default_char != FALSE {
+            return None;
+        }
+
+        let mut encoded = vec![0u8; length as usize];
+        let length = WideCharToMultiByte(
+            CP_ACP,
+            WC_NO_BEST_FIT_CHARS,
+            wide.as_ptr(),
+            wide.len() as i32,
+            encoded.as_mut_ptr(),
+            encoded.len() as i32,
+            null(),
+            &mut used_default_char,
+        );
+        if length <= 0 || used_default_char != FALSE {
+            return None;
+        }
+
+        encoded.truncate(length as usize);
+
+        Some(encoded)
+    }
+}